@@ -0,0 +1,119 @@
+//! Draws a triangle as the "scene" every frame and composites egui over it as a HUD.
+use egui_winit_wgpu_integrator::{egui, epi, wgpu, RunOptions};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Set explicitly so the scene pipeline can be created before the swap chain
+/// format is known.
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+
+const SHADER: &str = r#"
+[[builtin(vertex_index)]]
+var<in> in_vertex_index: u32;
+[[builtin(position)]]
+var<out> out_pos: vec4<f32>;
+
+[[stage(vertex)]]
+fn vs_main() {
+    var x: f32 = f32(i32(in_vertex_index) - 1);
+    var y: f32 = f32(i32(in_vertex_index & 1) * 2 - 1);
+    out_pos = vec4<f32>(x, y, 0.0, 1.0);
+}
+
+[[location(0)]]
+var<out> out_color: vec4<f32>;
+
+[[stage(fragment)]]
+fn fs_main() {
+    out_color = vec4<f32>(0.2, 0.6, 1.0, 1.0);
+}
+"#;
+
+fn create_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        flags: wgpu::ShaderFlags::all(),
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: None,
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("scene"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[FORMAT.into()],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+    })
+}
+
+struct HudApp {
+    clicks: u32,
+}
+
+impl epi::App for HudApp {
+    fn update(&mut self, ctx: &egui::CtxRef, _frame: &mut epi::Frame<'_>) {
+        egui::Window::new("HUD").show(ctx, |ui| {
+            ui.label("The triangle behind this window is drawn by the on_render hook.");
+            if ui.button("Click").clicked() {
+                self.clicks += 1;
+            }
+            ui.label(format!("Clicked {} times", self.clicks));
+        });
+    }
+
+    fn name(&self) -> &str {
+        "custom render"
+    }
+}
+
+fn main() {
+    // Built on first use, and again after a device loss since it belongs to
+    // the lost device.
+    let pipeline = Rc::new(RefCell::new(None));
+    let lost_pipeline = pipeline.clone();
+    let options = RunOptions {
+        output_format: Some(FORMAT),
+        // The scene pass clears the frame, egui must keep what it drew.
+        clear_color: None,
+        on_render: Some(Box::new(move |device, _queue, encoder, view| {
+            let mut pipeline = pipeline.borrow_mut();
+            let pipeline = pipeline.get_or_insert_with(|| create_pipeline(device));
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("scene"),
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.05,
+                            g: 0.05,
+                            b: 0.1,
+                            a: 1.0,
+                        }),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.draw(0..3, 0..1);
+        })),
+        on_device_lost: Some(Box::new(move || *lost_pipeline.borrow_mut() = None)),
+        ..Default::default()
+    };
+
+    egui_winit_wgpu_integrator::run_with_options(Box::new(HudApp { clicks: 0 }), options);
+}
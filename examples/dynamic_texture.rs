@@ -0,0 +1,92 @@
+//! Uploads a new native texture every frame and frees the one it replaces.
+use egui_winit_wgpu_integrator::epi::TextureAllocator;
+use egui_winit_wgpu_integrator::{egui, epi, wgpu, RunOptions};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Instant;
+
+const SIZE: u32 = 256;
+
+fn create_gradient(device: &wgpu::Device, queue: &wgpu::Queue, phase: f32) -> wgpu::Texture {
+    let size = wgpu::Extent3d {
+        width: SIZE,
+        height: SIZE,
+        depth: 1,
+    };
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("gradient"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+    });
+    let mut pixels = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let wave = ((x as f32 / SIZE as f32 + phase) * std::f32::consts::TAU).sin();
+            pixels.extend_from_slice(&[
+                ((wave * 0.5 + 0.5) * 255.0) as u8,
+                (y * 255 / SIZE) as u8,
+                128,
+                255,
+            ]);
+        }
+    }
+    queue.write_texture(
+        wgpu::TextureCopyView {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+        },
+        &pixels,
+        wgpu::TextureDataLayout {
+            offset: 0,
+            bytes_per_row: SIZE * 4,
+            rows_per_image: SIZE,
+        },
+        size,
+    );
+    texture
+}
+
+struct DynamicTextureApp {
+    texture_id: Rc<Cell<Option<egui::TextureId>>>,
+}
+
+impl epi::App for DynamicTextureApp {
+    fn update(&mut self, ctx: &egui::CtxRef, _frame: &mut epi::Frame<'_>) {
+        egui::Window::new("Dynamic texture").show(ctx, |ui| {
+            if let Some(texture_id) = self.texture_id.get() {
+                ui.image(texture_id, [SIZE as f32, SIZE as f32]);
+            }
+        });
+        // Keep animating.
+        ctx.request_repaint();
+    }
+
+    fn name(&self) -> &str {
+        "dynamic texture"
+    }
+}
+
+fn main() {
+    let texture_id = Rc::new(Cell::new(None));
+    let app = Box::new(DynamicTextureApp {
+        texture_id: texture_id.clone(),
+    });
+    let start = Instant::now();
+    let options = RunOptions {
+        on_update_textures: Some(Box::new(move |device, queue, render_pass| {
+            let texture = create_gradient(device, queue, start.elapsed().as_secs_f32() * 0.25);
+            let new_id = render_pass.egui_texture_from_wgpu_texture(device, &texture);
+            if let Some(old_id) = texture_id.replace(Some(new_id)) {
+                render_pass.free(old_id);
+            }
+        })),
+        ..Default::default()
+    };
+
+    egui_winit_wgpu_integrator::run_with_options(app, options);
+}
@@ -0,0 +1,46 @@
+//! Shows text in several scripts using fallback fonts.
+//!
+//! Pass the font files to use, e.g.:
+//! `cargo run --example fallback_fonts -- NotoSansJP-Regular.otf NotoEmoji-Regular.ttf`
+use egui_winit_wgpu_integrator::{egui, epi, ExtraFont, FontPriority, RunOptions};
+
+struct FallbackFontsApp;
+
+impl epi::App for FallbackFontsApp {
+    fn update(&mut self, ctx: &egui::CtxRef, _frame: &mut epi::Frame<'_>) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Fallback fonts");
+            ui.label("Latin: The quick brown fox jumps over the lazy dog.");
+            ui.label("Japanese: いろはにほへと 色は匂へど");
+            ui.label("Emoji: 🦀 🎉 ✅");
+        });
+    }
+
+    fn name(&self) -> &str {
+        "fallback fonts"
+    }
+}
+
+fn main() {
+    let mut extra_fonts = Vec::new();
+    for path in std::env::args().skip(1) {
+        match std::fs::read(&path) {
+            Ok(data) => {
+                for &family in &[egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+                    extra_fonts.push(ExtraFont {
+                        name: path.clone(),
+                        data: data.clone().into(),
+                        family,
+                        priority: FontPriority::Fallback,
+                    });
+                }
+            }
+            Err(e) => eprintln!("Failed to read {}: {}", path, e),
+        }
+    }
+    let options = RunOptions {
+        extra_fonts,
+        ..Default::default()
+    };
+    egui_winit_wgpu_integrator::run_with_options(Box::new(FallbackFontsApp), options);
+}
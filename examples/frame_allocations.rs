@@ -0,0 +1,96 @@
+//! Counts heap allocations per frame while redrawing the egui demo continuously.
+//!
+//! Run with `cargo run --release --example frame_allocations` and open a few
+//! demo windows for a busy UI. Every 100 frames the average number of
+//! allocations per frame is printed, split into `App::update` and everything
+//! else the integration does per frame (input, tessellation, uploads, ...).
+//! The first line also includes startup.
+//!
+//! This only measures. The integration's own per-frame work (encoder label,
+//! frame history, window requests) hardly allocates; most of what is counted
+//! comes from egui's tessellation and egui_wgpu_backend's uploads, which this
+//! crate can't change. No before/after numbers have been taken.
+use egui_winit_wgpu_integrator::{egui, epi, RunOptions};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const FRAMES: usize = 100;
+
+/// Counts the allocations made inside its app's `update`.
+struct CountedApp {
+    app: egui_demo_lib::WrapApp,
+    app_allocations: std::rc::Rc<std::cell::Cell<usize>>,
+}
+
+impl epi::App for CountedApp {
+    fn update(&mut self, ctx: &egui::CtxRef, frame: &mut epi::Frame<'_>) {
+        let start = ALLOCATIONS.load(Ordering::Relaxed);
+        self.app.update(ctx, frame);
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - start;
+        self.app_allocations
+            .set(self.app_allocations.get() + allocations);
+    }
+
+    fn setup(&mut self, ctx: &egui::CtxRef) {
+        self.app.setup(ctx);
+    }
+
+    fn name(&self) -> &str {
+        "frame allocations"
+    }
+}
+
+fn main() {
+    let app_allocations = std::rc::Rc::new(std::cell::Cell::new(0));
+    let app = CountedApp {
+        app: egui_demo_lib::WrapApp::default(),
+        app_allocations: app_allocations.clone(),
+    };
+    let mut frames = 0;
+    let mut last_total = ALLOCATIONS.load(Ordering::Relaxed);
+    let options = RunOptions {
+        continuous: true,
+        on_frame_timings: Some(Box::new(move |_timings| {
+            frames += 1;
+            if frames == FRAMES {
+                let total = ALLOCATIONS.load(Ordering::Relaxed);
+                let all = total - last_total;
+                let app = app_allocations.replace(0);
+                println!(
+                    "allocations per frame: {} total, {} in App::update, {} elsewhere",
+                    all / FRAMES,
+                    app / FRAMES,
+                    (all - app) / FRAMES
+                );
+                last_total = total;
+                frames = 0;
+            }
+        })),
+        ..Default::default()
+    };
+
+    egui_winit_wgpu_integrator::run_with_options(Box::new(app), options);
+}
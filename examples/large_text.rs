@@ -0,0 +1,33 @@
+//! Enlarges all text without defining custom fonts.
+use egui_winit_wgpu_integrator::{egui, epi, RunOptions};
+
+struct LargeTextApp {
+    text: String,
+}
+
+impl epi::App for LargeTextApp {
+    fn update(&mut self, ctx: &egui::CtxRef, _frame: &mut epi::Frame<'_>) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Heading");
+            ui.label("Body text at 20 points.");
+            ui.text_edit_singleline(&mut self.text);
+        });
+    }
+
+    fn name(&self) -> &str {
+        "large text"
+    }
+}
+
+fn main() {
+    let options = RunOptions {
+        default_text_size: Some(20.0),
+        ..Default::default()
+    };
+    egui_winit_wgpu_integrator::run_with_options(
+        Box::new(LargeTextApp {
+            text: String::new(),
+        }),
+        options,
+    );
+}
@@ -0,0 +1,135 @@
+//! Renders a triangle into a wgpu texture once at startup and shows it in an egui window.
+use egui_winit_wgpu_integrator::{egui, epi, wgpu, RunOptions};
+use std::cell::Cell;
+use std::rc::Rc;
+
+const SIZE: u32 = 256;
+
+const SHADER: &str = r#"
+[[builtin(vertex_index)]]
+var<in> in_vertex_index: u32;
+[[builtin(position)]]
+var<out> out_pos: vec4<f32>;
+
+[[stage(vertex)]]
+fn vs_main() {
+    var x: f32 = f32(i32(in_vertex_index) - 1);
+    var y: f32 = f32(i32(in_vertex_index & 1) * 2 - 1);
+    out_pos = vec4<f32>(x, y, 0.0, 1.0);
+}
+
+[[location(0)]]
+var<out> out_color: vec4<f32>;
+
+[[stage(fragment)]]
+fn fs_main() {
+    out_color = vec4<f32>(1.0, 0.0, 0.0, 1.0);
+}
+"#;
+
+fn render_triangle(device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Texture {
+    let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("triangle"),
+        size: wgpu::Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        flags: wgpu::ShaderFlags::all(),
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: None,
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("triangle"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[format.into()],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("triangle encoder"),
+    });
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("triangle"),
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&pipeline);
+        render_pass.draw(0..3, 0..1);
+    }
+    queue.submit(std::iter::once(encoder.finish()));
+    texture
+}
+
+struct TriangleApp {
+    texture_id: Rc<Cell<Option<egui::TextureId>>>,
+}
+
+impl epi::App for TriangleApp {
+    fn update(&mut self, ctx: &egui::CtxRef, _frame: &mut epi::Frame<'_>) {
+        egui::Window::new("Native texture").show(ctx, |ui| match self.texture_id.get() {
+            Some(texture_id) => {
+                ui.image(texture_id, [SIZE as f32, SIZE as f32]);
+            }
+            None => {
+                ui.label("Texture not registered");
+            }
+        });
+    }
+
+    fn name(&self) -> &str {
+        "native texture"
+    }
+}
+
+fn main() {
+    let texture_id = Rc::new(Cell::new(None));
+    let app = Box::new(TriangleApp {
+        texture_id: texture_id.clone(),
+    });
+    let options = RunOptions {
+        on_gpu_setup: Some(Box::new(move |device, queue, render_pass| {
+            let texture = render_triangle(device, queue);
+            texture_id.set(Some(
+                render_pass.egui_texture_from_wgpu_texture(device, &texture),
+            ));
+        })),
+        ..Default::default()
+    };
+
+    egui_winit_wgpu_integrator::run_with_options(app, options);
+}
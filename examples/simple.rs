@@ -0,0 +1,13 @@
+use egui_winit_wgpu_integrator::egui;
+
+fn main() {
+    let mut counter = 0;
+    egui_winit_wgpu_integrator::run_simple("simple", move |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if ui.button("Click me").clicked() {
+                counter += 1;
+            }
+            ui.label(format!("Clicked {} times", counter));
+        });
+    });
+}
@@ -0,0 +1,45 @@
+use crate::egui::CursorIcon;
+use winit::window::Window;
+
+/// Show the cursor egui asks for, hiding it for `CursorIcon::None`. The icon
+/// still changes while `hidden_by_app`, but the cursor stays hidden.
+pub(crate) fn set_cursor_icon(window: &Window, cursor_icon: CursorIcon, hidden_by_app: bool) {
+    match translate_cursor(cursor_icon) {
+        Some(cursor_icon) => {
+            window.set_cursor_visible(!hidden_by_app);
+            window.set_cursor_icon(cursor_icon);
+        }
+        None => window.set_cursor_visible(false),
+    }
+}
+
+fn translate_cursor(cursor_icon: CursorIcon) -> Option<winit::window::CursorIcon> {
+    use winit::window::CursorIcon as Winit;
+    Some(match cursor_icon {
+        CursorIcon::None => return None,
+        CursorIcon::Alias => Winit::Alias,
+        CursorIcon::AllScroll => Winit::AllScroll,
+        CursorIcon::Cell => Winit::Cell,
+        CursorIcon::ContextMenu => Winit::ContextMenu,
+        CursorIcon::Copy => Winit::Copy,
+        CursorIcon::Crosshair => Winit::Crosshair,
+        CursorIcon::Default => Winit::Default,
+        CursorIcon::Grab => Winit::Grab,
+        CursorIcon::Grabbing => Winit::Grabbing,
+        CursorIcon::Help => Winit::Help,
+        CursorIcon::Move => Winit::Move,
+        CursorIcon::NoDrop => Winit::NoDrop,
+        CursorIcon::NotAllowed => Winit::NotAllowed,
+        CursorIcon::PointingHand => Winit::Hand,
+        CursorIcon::Progress => Winit::Progress,
+        CursorIcon::ResizeHorizontal => Winit::EwResize,
+        CursorIcon::ResizeNeSw => Winit::NeswResize,
+        CursorIcon::ResizeNwSe => Winit::NwseResize,
+        CursorIcon::ResizeVertical => Winit::NsResize,
+        CursorIcon::Text => Winit::Text,
+        CursorIcon::VerticalText => Winit::VerticalText,
+        CursorIcon::Wait => Winit::Wait,
+        CursorIcon::ZoomIn => Winit::ZoomIn,
+        CursorIcon::ZoomOut => Winit::ZoomOut,
+    })
+}
@@ -0,0 +1,19 @@
+//! Logging of frames dropped because the swap chain had no texture to hand out.
+use crate::wgpu::SwapChainError;
+
+/// Log a dropped frame at a level matching how unusual the error is.
+pub(crate) fn log(error: &SwapChainError) {
+    match error {
+        // Happen routinely on resize or GPU resets and are fixed by recreating the swap chain.
+        SwapChainError::Outdated | SwapChainError::Lost => {
+            log::debug!("Dropped frame with error: {}", error)
+        }
+        SwapChainError::Timeout => log::warn!("Dropped frame with error: {}", error),
+        SwapChainError::OutOfMemory => log::error!("Dropped frame with error: {}", error),
+    }
+}
+
+/// Whether recreating the swap chain makes the next frame succeed.
+pub(crate) fn needs_new_swap_chain(error: &SwapChainError) -> bool {
+    matches!(error, SwapChainError::Outdated | SwapChainError::Lost)
+}
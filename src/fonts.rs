@@ -0,0 +1,101 @@
+use crate::egui::{FontDefinitions, FontFamily};
+use std::borrow::Cow;
+
+/// Where a font goes in the list of fonts of its family.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FontPriority {
+    /// Tried before all fonts already in the family.
+    Highest,
+    /// Only used for glyphs the fonts already in the family lack, e.g. for
+    /// Japanese or emoji next to a Latin font.
+    Fallback,
+}
+
+/// A font added to [`crate::RunOptions::font_definitions`] at startup.
+#[derive(Clone, Debug)]
+pub struct ExtraFont {
+    /// Key of the font in `FontDefinitions::font_data`.
+    pub name: String,
+    /// Contents of a TTF or OTF file.
+    pub data: Cow<'static, [u8]>,
+    pub family: FontFamily,
+    pub priority: FontPriority,
+}
+
+/// Register `data` under `name` and add it to `family`.
+///
+/// Fonts added as [`FontPriority::Fallback`] are tried in the order they were added.
+pub fn add_font(
+    font_definitions: &mut FontDefinitions,
+    name: &str,
+    data: impl Into<Cow<'static, [u8]>>,
+    family: FontFamily,
+    priority: FontPriority,
+) {
+    font_definitions
+        .font_data
+        .insert(name.to_owned(), data.into());
+    let fonts = font_definitions.fonts_for_family.entry(family).or_default();
+    fonts.retain(|font| font != name);
+    match priority {
+        FontPriority::Highest => fonts.insert(0, name.to_owned()),
+        FontPriority::Fallback => fonts.push(name.to_owned()),
+    }
+}
+
+pub(crate) fn add_fonts(
+    mut font_definitions: FontDefinitions,
+    fonts: Vec<ExtraFont>,
+) -> FontDefinitions {
+    for font in fonts {
+        add_font(
+            &mut font_definitions,
+            &font.name,
+            font.data,
+            font.family,
+            font.priority,
+        );
+    }
+    font_definitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_font_places_fonts_by_priority() {
+        let mut font_definitions = FontDefinitions::default();
+        let data: &'static [u8] = &[0];
+        add_font(
+            &mut font_definitions,
+            "fallback",
+            data,
+            FontFamily::Proportional,
+            FontPriority::Fallback,
+        );
+        add_font(
+            &mut font_definitions,
+            "highest",
+            data,
+            FontFamily::Proportional,
+            FontPriority::Highest,
+        );
+        let fonts = &font_definitions.fonts_for_family[&FontFamily::Proportional];
+        assert_eq!(fonts.first().map(String::as_str), Some("highest"));
+        assert_eq!(fonts.last().map(String::as_str), Some("fallback"));
+        assert!(font_definitions.font_data.contains_key("fallback"));
+
+        // Adding a font again moves it instead of listing it twice.
+        add_font(
+            &mut font_definitions,
+            "fallback",
+            data,
+            FontFamily::Proportional,
+            FontPriority::Highest,
+        );
+        let fonts = &font_definitions.fonts_for_family[&FontFamily::Proportional];
+        assert_eq!(fonts.first().map(String::as_str), Some("fallback"));
+        assert_eq!(fonts.iter().filter(|font| *font == "fallback").count(), 1);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// CPU time spent on the most recent frames, in seconds, oldest first.
+///
+/// Measured the same way as `IntegrationInfo::cpu_usage`.
+#[derive(Clone, Debug)]
+pub struct FrameHistory {
+    frame_times: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl Default for FrameHistory {
+    fn default() -> Self {
+        Self::new(120)
+    }
+}
+
+impl FrameHistory {
+    /// Keeps the times of the last `capacity` frames.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frame_times: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, frame_time: f32) {
+        if self.frame_times.len() == self.capacity {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+
+    pub fn frame_times(&self) -> impl Iterator<Item = f32> + '_ {
+        self.frame_times.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.frame_times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frame_times.is_empty()
+    }
+
+    pub fn min(&self) -> Option<f32> {
+        self.frame_times()
+            .fold(None, |min, t| Some(min.map_or(t, |min: f32| min.min(t))))
+    }
+
+    pub fn max(&self) -> Option<f32> {
+        self.frame_times()
+            .fold(None, |max, t| Some(max.map_or(t, |max: f32| max.max(t))))
+    }
+
+    pub fn average(&self) -> Option<f32> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.frame_times().sum::<f32>() / self.len() as f32)
+        }
+    }
+}
+
+/// How long the phases of the last frame took on the CPU.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameTimings {
+    /// `App::update`, including egui's layout.
+    pub update: Duration,
+    /// Turning egui's shapes into meshes.
+    pub tessellate: Duration,
+    /// Uploading the font atlas, user textures and meshes.
+    pub upload: Duration,
+    /// Submitting the frame's commands to the GPU queue.
+    pub submit: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_most_recent_frames() {
+        let mut history = FrameHistory::new(3);
+        assert!(history.is_empty());
+        assert_eq!(history.average(), None);
+        for frame_time in &[4.0, 1.0, 2.0, 3.0] {
+            history.push(*frame_time);
+        }
+        assert_eq!(
+            history.frame_times().collect::<Vec<_>>(),
+            vec![1.0, 2.0, 3.0]
+        );
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.min(), Some(1.0));
+        assert_eq!(history.max(), Some(3.0));
+        assert_eq!(history.average(), Some(2.0));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::readback::Readback;
+use crate::{epi, wgpu, EguiRenderer, GpuOptions, RunError, ScreenDescriptor};
+use egui_wgpu_backend::epi::IntegrationInfo;
+use egui_winit_platform::PlatformDescriptor;
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+struct NoopRepaintSignal;
+impl epi::RepaintSignal for NoopRepaintSignal {
+    fn request_repaint(&self) {}
+}
+
+/// Render a single frame of `app` without opening a window.
+///
+/// `width` and `height` are in physical pixels. Runs `App::setup` followed by one
+/// `App::update`, so a fresh app renders the same image every time, which makes
+/// this usable for golden-image tests.
+pub fn render_to_image(
+    app: &mut dyn epi::App,
+    width: u32,
+    height: u32,
+    scale_factor: f64,
+) -> Result<image::RgbaImage, RunError> {
+    // Headless rendering mostly runs on CI, which often has no GPU.
+    let gpu = GpuOptions {
+        allow_software_fallback: true,
+        ..Default::default()
+    };
+    let instance = wgpu::Instance::new(gpu.backends);
+    let adapter = crate::request_adapter(&instance, &gpu, None)?;
+    let (device, queue) = crate::request_device(&adapter, gpu.features, gpu.limits)?;
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("egui headless target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut renderer = EguiRenderer::new(
+        &device,
+        FORMAT,
+        PlatformDescriptor {
+            physical_width: width,
+            physical_height: height,
+            scale_factor,
+            font_definitions: Default::default(),
+            style: Default::default(),
+        },
+    );
+    app.setup(&renderer.context());
+
+    let info = IntegrationInfo {
+        web_info: None,
+        cpu_usage: None,
+        seconds_since_midnight: None,
+        native_pixels_per_point: Some(scale_factor as _),
+    };
+    renderer.update(app, 0.0, info, std::sync::Arc::new(NoopRepaintSignal));
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("egui headless encoder"),
+    });
+    let screen_descriptor = ScreenDescriptor {
+        physical_width: width,
+        physical_height: height,
+        scale_factor: scale_factor as f32,
+    };
+    renderer.render(
+        &device,
+        &queue,
+        &mut encoder,
+        &view,
+        &screen_descriptor,
+        Some(wgpu::Color::BLACK),
+    );
+    let readback = Readback::new(&device, width, height);
+    readback.copy_from(&mut encoder, &texture);
+    queue.submit(std::iter::once(encoder.finish()));
+
+    readback.read(&device).ok_or(RunError::Readback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::egui;
+
+    /// Paints a white square over the black background.
+    struct Square;
+
+    impl epi::App for Square {
+        fn update(&mut self, ctx: &egui::CtxRef, _frame: &mut epi::Frame<'_>) {
+            ctx.layer_painter(egui::LayerId::background()).rect_filled(
+                egui::Rect::from_min_max(egui::pos2(4.0, 4.0), egui::pos2(12.0, 12.0)),
+                0.0,
+                egui::Color32::WHITE,
+            );
+        }
+
+        fn name(&self) -> &str {
+            "square"
+        }
+    }
+
+    /// Frames are single-sampled (the egui pipeline can't multisample), so an
+    /// axis-aligned square on whole pixels has hard edges: egui's feathering
+    /// ends exactly between pixel centers and leaves no partially covered
+    /// pixels, unlike a multisample resolve would.
+    #[test]
+    fn square_has_hard_edges() {
+        let image = match render_to_image(&mut Square, 16, 16, 1.0) {
+            Ok(image) => image,
+            // Nothing to test on machines without any adapter, not even a software one.
+            Err(RunError::NoAdapter) => return,
+            Err(e) => panic!("{}", e),
+        };
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let inside = (4..12).contains(&x) && (4..12).contains(&y);
+            let expected = if inside {
+                [255, 255, 255, 255]
+            } else {
+                [0, 0, 0, 255]
+            };
+            assert_eq!(pixel.0, expected, "pixel {}, {}", x, y);
+        }
+    }
+}
@@ -1,181 +1,2145 @@
 use chrono::Timelike;
 use egui_wgpu_backend::epi::backend::AppOutput;
+pub use egui_wgpu_backend::epi::egui;
 use egui_wgpu_backend::epi::IntegrationInfo;
+#[cfg(feature = "persistence")]
+use egui_wgpu_backend::epi::Storage;
 use egui_wgpu_backend::wgpu::{
     BackendBit, CommandEncoderDescriptor, DeviceDescriptor, Features, Instance, Limits,
     PowerPreference, PresentMode, RequestAdapterOptions, SwapChainDescriptor, TextureUsage,
 };
-use egui_wgpu_backend::{epi, wgpu, RenderPass, ScreenDescriptor};
-use egui_winit_platform::{Platform, PlatformDescriptor};
+pub use egui_wgpu_backend::{epi, wgpu, RenderPass, ScreenDescriptor};
+pub use egui_winit_platform::{Platform, PlatformDescriptor};
 use futures_lite::future::block_on;
 use std::time::Instant;
 use winit::event::WindowEvent;
 use winit::event_loop::ControlFlow;
 
-struct RequestRepaintEvent;
-struct WgpuRepaintSignal(std::sync::Mutex<winit::event_loop::EventLoopProxy<RequestRepaintEvent>>);
-impl epi::RepaintSignal for WgpuRepaintSignal {
+#[cfg(feature = "persistence")]
+mod persistence;
+#[cfg(feature = "persistence")]
+mod window_settings;
+#[cfg(feature = "persistence")]
+pub use window_settings::WindowSettings;
+mod cursor;
+mod dropped_frame;
+mod fonts;
+pub use fonts::{add_font, ExtraFont, FontPriority};
+mod frame_history;
+mod monitor;
+mod picking;
+pub use frame_history::{FrameHistory, FrameTimings};
+pub use picking::PickingTarget;
+mod headless;
+pub use headless::render_to_image;
+mod readback;
+mod renderer;
+mod scaled_target;
+mod screenshot;
+mod time_source;
+pub use renderer::EguiRenderer;
+pub use time_source::{ManualTime, TimeSource};
+mod window_handle;
+pub use window_handle::WindowHandle;
+mod window_manager;
+pub use window_manager::{run_windows, WindowManager, WindowUi};
+
+/// Events sent to the event loop through its `EventLoopProxy`.
+#[derive(Debug)]
+pub enum UserEvent<T = ()> {
+    /// Redraw the window. Sent by the integration when the app requests a repaint.
+    RequestRepaint,
+    /// An event of the caller, handed to [`RunOptions::on_user_event`].
+    Custom(T),
+}
+
+/// Asks the UI to redraw, from any thread.
+///
+/// It's cheap to clone and `Send` (as long as `T` is), so a file watcher or
+/// socket reader can keep its own copy. Create it from the event loop passed to
+/// [`run_with_event_loop`].
+pub struct RepaintHandle<T: 'static = ()>(winit::event_loop::EventLoopProxy<UserEvent<T>>);
+
+impl<T: 'static> Clone for RepaintHandle<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: 'static> RepaintHandle<T> {
+    pub fn new(event_loop: &winit::event_loop::EventLoop<UserEvent<T>>) -> Self {
+        Self(event_loop.create_proxy())
+    }
+
+    /// Does nothing once the event loop exited.
+    pub fn request_repaint(&self) {
+        self.0.send_event(UserEvent::RequestRepaint).ok();
+    }
+}
+
+struct WgpuRepaintSignal<T: 'static>(std::sync::Mutex<RepaintHandle<T>>);
+impl<T: Send + 'static> epi::RepaintSignal for WgpuRepaintSignal<T> {
     fn request_repaint(&self) {
-        self.0.lock().unwrap().send_event(RequestRepaintEvent).ok();
+        self.0.lock().unwrap().request_repaint();
     }
 }
-const OUTPUT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
-pub fn run(mut app: Box<dyn epi::App>) -> ! {
-    let event_loop = winit::event_loop::EventLoop::with_user_event();
-    let name = app.name();
-    let window = winit::window::WindowBuilder::new()
-        .with_title(name)
-        .build(&event_loop)
-        .unwrap();
-    let instance = Instance::new(BackendBit::PRIMARY);
 
-    let surface = unsafe { instance.create_surface(&window) };
+/// Errors that can occur while setting up the window and GPU in [`try_run`].
+#[derive(Debug)]
+pub enum RunError {
+    /// No adapter compatible with the window surface was found.
+    NoAdapter,
+    /// The adapter lacks some of the requested features, which are listed here.
+    UnsupportedFeatures(wgpu::Features),
+    /// The adapter refused to create a device.
+    DeviceRequest(wgpu::RequestDeviceError),
+    /// The window could not be built.
+    WindowBuild(winit::error::OsError),
+    /// The surface could not be created for the window.
+    ///
+    /// Never returned with wgpu 0.7, where creating a surface can't fail.
+    SurfaceCreation,
+    /// A rendered image could not be copied back from the GPU.
+    Readback,
+}
 
-    let adapter = block_on(instance.request_adapter(&RequestAdapterOptions {
-        power_preference: PowerPreference::HighPerformance,
-        compatible_surface: Some(&surface),
-    }))
-    .unwrap();
+impl std::fmt::Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunError::NoAdapter => write!(f, "no compatible graphics adapter found"),
+            RunError::UnsupportedFeatures(features) => {
+                write!(f, "adapter doesn't support features {:?}", features)
+            }
+            RunError::DeviceRequest(e) => write!(f, "failed to request device: {}", e),
+            RunError::WindowBuild(e) => write!(f, "failed to build window: {}", e),
+            RunError::SurfaceCreation => write!(f, "failed to create surface"),
+            RunError::Readback => write!(f, "failed to read back rendered image"),
+        }
+    }
+}
+
+impl std::error::Error for RunError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RunError::DeviceRequest(e) => Some(e),
+            RunError::WindowBuild(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Options used to pick the GPU adapter and create the device.
+#[derive(Clone, Debug)]
+pub struct GpuOptions {
+    /// Backends to request an adapter from.
+    ///
+    /// Passing `BackendBit::all()` also considers secondary backends, which
+    /// allows falling back to an adapter when no primary backend is available.
+    pub backends: BackendBit,
+    /// Whether to prefer the integrated or the discrete GPU.
+    pub power_preference: PowerPreference,
+    /// Features the device is created with, e.g. for custom rendering.
+    pub features: Features,
+    /// Limits the device is created with.
+    pub limits: Limits,
+    /// If no adapter satisfies the request, fall back to a software rasterizer
+    /// (or, failing that, any adapter) instead of failing with
+    /// [`RunError::NoAdapter`]. Useful on CI runners and VMs without GPU drivers.
+    pub allow_software_fallback: bool,
+}
 
-    let (device, queue) = block_on(adapter.request_device(
-        &DeviceDescriptor {
+impl Default for GpuOptions {
+    fn default() -> Self {
+        Self {
+            backends: BackendBit::PRIMARY,
+            power_preference: PowerPreference::HighPerformance,
             features: Features::default(),
             limits: Limits::default(),
-            label: None,
+            allow_software_fallback: false,
+        }
+    }
+}
+
+/// How the window covers the screen when fullscreen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FullscreenMode {
+    /// Switch the monitor to its largest video mode.
+    Exclusive,
+    /// Cover the monitor with a borderless window without changing its video mode.
+    Borderless,
+}
+
+impl FullscreenMode {
+    fn to_winit(
+        self,
+        monitor: Option<winit::monitor::MonitorHandle>,
+    ) -> Option<winit::window::Fullscreen> {
+        if self == FullscreenMode::Exclusive {
+            let video_mode = monitor.as_ref().and_then(|monitor| {
+                monitor.video_modes().max_by_key(|mode| {
+                    let size = mode.size();
+                    (size.width * size.height, mode.refresh_rate())
+                })
+            });
+            match video_mode {
+                Some(video_mode) => return Some(winit::window::Fullscreen::Exclusive(video_mode)),
+                None => log::warn!("No video mode for exclusive fullscreen, using borderless"),
+            }
+        }
+        Some(winit::window::Fullscreen::Borderless(monitor))
+    }
+}
+
+/// Which scale is reported to the app as `IntegrationInfo::native_pixels_per_point`.
+///
+/// With a [`RunOptions::zoom_factor`] other than 1, the display scale and the
+/// scale egui lays out with differ. Apps comparing it to `ctx.pixels_per_point()`
+/// want [`Self::Effective`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportedScale {
+    /// Physical pixels per point of the display: the scale factor of the OS, or
+    /// [`RunOptions::force_scale_factor`]. Ignores the zoom.
+    Display,
+    /// The display scale multiplied by the zoom, i.e. what egui lays out with.
+    Effective,
+}
+
+impl ReportedScale {
+    fn pick(self, display: f64, effective: f64) -> f32 {
+        match self {
+            Self::Display => display as f32,
+            Self::Effective => effective as f32,
+        }
+    }
+}
+
+/// UI zoom on top of the display's scale factor, shared with the integration.
+///
+/// Clone it into the app and call [`Self::set`] (e.g. on Ctrl+/Ctrl-) to
+/// scale the whole UI without changing the size of the swap chain.
+///
+/// Three scales are involved while zoomed:
+/// - the display scale, physical pixels per logical pixel of the OS (or
+///   [`RunOptions::force_scale_factor`]), which the zoom doesn't change;
+/// - egui's `pixels_per_point`, the display scale times the zoom, which is what
+///   widgets are laid out and input is mapped with;
+/// - `ScreenDescriptor::scale_factor`, pixels of the render target per point.
+///   egui's meshes are in points, so this has to follow `pixels_per_point`
+///   (times [`RunOptions::render_scale`]); using the display scale here would
+///   draw the zoomed layout at the wrong size.
+///
+/// Apps get the display scale as `native_pixels_per_point` unless
+/// [`RunOptions::reported_scale`] says otherwise.
+#[derive(Clone, Debug)]
+pub struct ZoomFactor(std::rc::Rc<std::cell::Cell<f32>>);
+
+impl Default for ZoomFactor {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl ZoomFactor {
+    pub fn new(zoom_factor: f32) -> Self {
+        Self(std::rc::Rc::new(std::cell::Cell::new(zoom_factor)))
+    }
+
+    pub fn get(&self) -> f32 {
+        self.0.get()
+    }
+
+    /// Takes effect on the next frame.
+    pub fn set(&self, zoom_factor: f32) {
+        self.0.set(zoom_factor);
+    }
+}
+
+/// Raw window icon.
+#[derive(Clone, Debug)]
+pub struct IconData {
+    /// RGBA pixels, row by row.
+    pub rgba: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Files hovered over or dropped onto the window.
+///
+/// egui's `RawInput` has no file-drop fields yet, so these are handed to
+/// [`RunOptions::on_file_drop`] instead.
+#[derive(Clone, Debug, Default)]
+pub struct FileDrop {
+    /// Files currently hovered over the window.
+    pub hovered_files: Vec<std::path::PathBuf>,
+    /// Files dropped since the last frame.
+    pub dropped_files: Vec<std::path::PathBuf>,
+}
+
+impl FileDrop {
+    pub fn is_empty(&self) -> bool {
+        self.hovered_files.is_empty() && self.dropped_files.is_empty()
+    }
+}
+
+/// A key pressed together with exactly these modifiers.
+///
+/// egui 0.10 has no shortcut type of its own, so this is in winit's terms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyboardShortcut {
+    pub modifiers: winit::event::ModifiersState,
+    pub key: winit::event::VirtualKeyCode,
+}
+
+impl KeyboardShortcut {
+    /// Cmd+Q on macOS. Elsewhere the window manager closes the window (e.g. on
+    /// Alt+F4), which arrives as a close request.
+    pub fn platform_quit() -> Option<Self> {
+        if cfg!(target_os = "macos") {
+            Some(Self {
+                modifiers: winit::event::ModifiersState::LOGO,
+                key: winit::event::VirtualKeyCode::Q,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Options used to configure the window before the app starts running.
+///
+/// Sizes are in physical pixels. `T` is the type of custom [`UserEvent`]s.
+///
+/// # Anti-aliasing
+///
+/// There's no multisampling option: the egui pipeline of egui_wgpu_backend 0.5
+/// has a fixed sample count of 1, and egui anti-aliases its shapes by
+/// feathering their edges instead. Frames are always single-sampled, and so
+/// are screenshots. [`Self::render_scale`] above 1 supersamples the whole
+/// frame instead.
+pub struct RunOptions<T: 'static = ()> {
+    /// Initial inner size of the window in physical pixels. `None` lets the OS decide.
+    pub initial_size: Option<egui::Vec2>,
+    /// Initial inner size of the window in points, overriding [`Self::initial_size`].
+    ///
+    /// Points are converted with the primary monitor's scale factor (or
+    /// [`Self::force_scale_factor`]) before the window exists, so 800x600 points
+    /// open a 1600x1200 pixel window on a 2x display. A physical size opens
+    /// the same number of pixels everywhere, which looks small on HiDPI screens.
+    pub initial_size_points: Option<egui::Vec2>,
+    /// Initial outer position of the window. If it isn't on any monitor, the
+    /// window is centered on the primary monitor instead.
+    pub initial_pos: Option<egui::Pos2>,
+    /// Center the window on the primary monitor, unless [`Self::initial_pos`] is set.
+    pub center_on_start: bool,
+    /// Minimum inner size of the window in physical pixels.
+    /// `None` limits the window to [`Self::min_size_points`] instead.
+    pub min_size: Option<egui::Vec2>,
+    /// Minimum inner size of the window in points, so panels don't overlap and
+    /// scroll bars don't vanish in tiny windows. Also applies to sizes the app
+    /// requests with `Frame::set_window_size`. Defaults to 200x100.
+    pub min_size_points: Option<egui::Vec2>,
+    /// Maximum inner size of the window.
+    /// `None` limits the window to `App::max_size_points` instead, if the app
+    /// overrides it. epi's default is meant for web canvases and is ignored.
+    pub max_size: Option<egui::Vec2>,
+    /// Width divided by height to keep the inner size at while resizing, `None`
+    /// to resize freely. Resizes are snapped to the closest size with that ratio.
+    pub aspect_ratio: Option<f32>,
+    /// Show the title bar and borders.
+    pub decorated: bool,
+    /// Allow the user to resize the window.
+    pub resizable: bool,
+    /// Start with the window maximized.
+    pub maximized: bool,
+    /// Keep the window above other windows, e.g. for overlays. Can be changed
+    /// while running with [`WindowHandle::set_always_on_top`]. Not supported on
+    /// Wayland, where it only logs a warning.
+    pub always_on_top: bool,
+    /// Start fullscreen on the primary monitor.
+    pub fullscreen: Option<FullscreenMode>,
+    /// Key toggling fullscreen at runtime, using the mode of [`Self::fullscreen`]
+    /// or borderless if that is `None`.
+    pub fullscreen_toggle_key: Option<winit::event::VirtualKeyCode>,
+    /// Shortcut exiting the app, after `App::on_exit` and saving as on any other
+    /// exit. Defaults to [`KeyboardShortcut::platform_quit`]; set it to `None`
+    /// for apps that confirm before quitting.
+    pub quit_shortcut: Option<KeyboardShortcut>,
+    /// Number of frames the app is updated before the window is shown, so the
+    /// layout settles (fonts, sizes) before anything is visible. While warming
+    /// up, the window stays hidden and nothing is rendered.
+    pub warmup_frames: u32,
+    /// Keep the window hidden until the app shows it with
+    /// [`WindowHandle::set_visible`], e.g. after loading a large file in
+    /// `App::setup` or while a background thread loads and requests repaints.
+    /// The app is updated and rendered as usual while hidden.
+    pub start_hidden: bool,
+    /// Adapter selection.
+    pub gpu: GpuOptions,
+    /// GPU objects the app already created, e.g. for an existing wgpu renderer,
+    /// to reuse instead of creating new ones. The integration takes ownership;
+    /// hooks like [`Self::on_render`] get the device back. If the device is
+    /// lost, a new one is requested from the same instance.
+    pub shared_gpu: Option<SharedGpu>,
+    /// Present mode of the swap chain. Can be changed while running with
+    /// [`WindowHandle::set_present_mode`].
+    ///
+    /// wgpu doesn't expose which modes a surface supports, but it falls back to
+    /// `PresentMode::Fifo` (with a logged warning) when creating a swap chain
+    /// with an unsupported mode, so any value is safe to pass here.
+    ///
+    /// This is also the only knob for input latency: wgpu 0.7's swap chain has
+    /// no setting for the number of images or queued frames, which the backend
+    /// picks. `Fifo` waits for vsync and may queue a frame or two, `Mailbox`
+    /// replaces the queued frame with the newest one (no tearing, low latency)
+    /// and `Immediate` presents right away, possibly tearing.
+    pub present_mode: PresentMode,
+    /// Color the window is cleared to before egui is drawn.
+    /// `None` makes the egui pass load the existing contents of the frame, so
+    /// egui can be composited over a scene drawn by [`Self::on_render`].
+    pub clear_color: Option<egui::Rgba>,
+    /// Make the window background transparent so egui panels float over the
+    /// desktop. The window is then cleared to transparent instead of
+    /// [`Self::clear_color`], unless that is `None`.
+    ///
+    /// Whether the compositor blends the window depends on the platform.
+    pub transparent: bool,
+    /// Usage of the swap chain textures, always including `RENDER_ATTACHMENT`.
+    ///
+    /// Extra usages apply to the view [`Self::on_render`] gets, e.g. `STORAGE`
+    /// to write the frame from a compute shader. wgpu 0.7 only exposes that
+    /// view, not the texture, so frames can't be copied out whatever the usage;
+    /// screenshots render into a texture of their own instead. Extra usages can
+    /// keep the driver from using its fastest presentation path (compressed or
+    /// tiled images, direct scanout), so only add what's needed. Usages the
+    /// surface doesn't support make creating the swap chain fail.
+    pub swap_chain_usage: TextureUsage,
+    /// Resolution the frame is rendered at, relative to the window, e.g. 0.75 to
+    /// save fill rate on weak GPUs. The frame is then stretched over the window.
+    ///
+    /// Below 1 the UI gets blurry, text most noticeably; above 1 it's
+    /// supersampled at a higher cost. [`Self::on_render`] renders at this
+    /// resolution too. Input still maps to the full window.
+    pub render_scale: f32,
+    /// Texture format of the swap chain. `None` uses the format the surface prefers,
+    /// which also replaces formats swap chains can't render to.
+    pub output_format: Option<wgpu::TextureFormat>,
+    /// Taskbar and title bar icon.
+    pub icon: Option<IconData>,
+    /// Application id on Wayland and `WM_CLASS` on X11, which desktops use to
+    /// group windows and find the icon of the `.desktop` file. `None` keeps
+    /// winit's defaults. Ignored on other platforms.
+    pub app_id: Option<String>,
+    /// JSON file the window position and size are restored from on start and
+    /// saved to on exit. App storage is kept next to it, in `<stem>.app.json`
+    /// (e.g. `window.app.json` for `window.json`), and saved every
+    /// `App::auto_save_interval` as well as on exit.
+    /// `None` disables persistence.
+    #[cfg(feature = "persistence")]
+    pub window_settings_path: Option<std::path::PathBuf>,
+    /// Restore egui's widget state (open collapsing headers, scroll positions,
+    /// ...) from the app storage and save it on exit. Does nothing without
+    /// [`Self::window_settings_path`].
+    #[cfg(feature = "persistence")]
+    pub persist_egui_memory: bool,
+    /// Called before `App::update` while files are hovered or after they were dropped.
+    pub on_file_drop: Option<Box<dyn FnMut(&FileDrop)>>,
+    /// Upper bound on frames per second while egui keeps requesting repaints.
+    /// `None` caps at the monitor's refresh rate where it's known (see
+    /// [`WindowHandle::refresh_rate`]), `Some(0)` redraws as fast as possible.
+    pub max_fps: Option<u32>,
+    /// Redraw every frame, whether egui needs it or not, e.g. for games. Combine
+    /// with [`Self::max_fps`] for a steady frame rate.
+    pub continuous: bool,
+    /// Like [`Self::max_fps`], but while the window doesn't have focus, e.g. to
+    /// save battery in the background. Repaint requests still wake the window.
+    /// `None` keeps using [`Self::max_fps`].
+    pub unfocused_fps: Option<u32>,
+    /// Time without input after which [`Self::on_idle`] is called, or the app
+    /// exits if that is `None`, e.g. for kiosks. Repaints don't count as input.
+    pub idle_timeout: Option<std::time::Duration>,
+    /// Called each time [`Self::idle_timeout`] elapsed without input.
+    pub on_idle: Option<Box<dyn FnMut()>>,
+    /// Keep updating the app while the window is minimized, at
+    /// [`Self::unfocused_fps`], e.g. for tools ticking in the background.
+    ///
+    /// Only windows shrunk to zero size count as minimized, which is what
+    /// minimizing does on Windows; nothing is rendered into them. By default
+    /// the app pauses there until the window is restored. Elsewhere, e.g. on
+    /// macOS and X11, minimized windows keep their size and go on rendering
+    /// either way.
+    pub render_while_minimized: bool,
+    /// Reported to the app as `IntegrationInfo::web_info`, for apps embedded in
+    /// a host that wants them to behave as if running on the web.
+    pub web_info: Option<epi::WebInfo>,
+    /// Called before `App::update` with the times of recent frames.
+    pub on_frame_history: Option<Box<dyn FnMut(&FrameHistory)>>,
+    /// Called after each frame was submitted with how long its phases took, to
+    /// tell whether the app or the integration is the bottleneck.
+    pub on_frame_timings: Option<Box<dyn FnMut(&FrameTimings)>>,
+    /// Clock driving egui's animations. `None` uses the time since start.
+    pub time_source: Option<Box<dyn TimeSource>>,
+    /// Fonts available from the first frame on.
+    pub font_definitions: egui::FontDefinitions,
+    /// Fonts added to [`Self::font_definitions`], in order, e.g. fallbacks for
+    /// scripts the default fonts don't cover.
+    pub extra_fonts: Vec<ExtraFont>,
+    /// Size in points of body text. All text styles of [`Self::font_definitions`]
+    /// are scaled by the same factor, so headings stay larger than body text.
+    pub default_text_size: Option<f32>,
+    /// Style used from the first frame on.
+    pub style: egui::Style,
+    /// Switch between dark and light visuals with the OS theme, at startup and
+    /// whenever it changes, replacing the visuals of [`Self::style`]. winit 0.24
+    /// only reports the theme on Windows; elsewhere the style is kept.
+    pub follow_system_theme: bool,
+    /// Changes the window on the app's behalf while running.
+    pub window_handle: WindowHandle,
+    /// Zoom applied to the whole UI, adjustable while running.
+    pub zoom_factor: ZoomFactor,
+    /// Which scale the app sees as `IntegrationInfo::native_pixels_per_point`.
+    pub reported_scale: ReportedScale,
+    /// Scale factor used instead of the one the OS reports for the window,
+    /// e.g. for consistent screenshots or remote sessions reporting a wrong DPI.
+    /// Stays in effect when the window moves between monitors.
+    pub force_scale_factor: Option<f64>,
+    /// Name of the egui work in GPU debuggers like RenderDoc or PIX: the frame's
+    /// encoder is labeled `"<debug_label> encoder"` and the egui pass is wrapped
+    /// in a debug group named `debug_label`.
+    pub debug_label: String,
+    /// Records custom rendering into the frame's encoder.
+    ///
+    /// Runs right before the egui pass, so egui is drawn on top of whatever
+    /// the hook renders. Set [`Self::clear_color`] to `None`, otherwise the
+    /// egui pass clears the hook's output.
+    ///
+    /// Pipelines, buffers and textures the hook creates belong to the device it
+    /// is given. After the device was lost ([`Self::on_device_lost`]) the hook
+    /// gets a new one and has to create them again.
+    #[allow(clippy::type_complexity)]
+    pub on_render: Option<
+        Box<dyn FnMut(&wgpu::Device, &wgpu::Queue, &mut wgpu::CommandEncoder, &wgpu::TextureView)>,
+    >,
+    /// Format of an extra window-sized target for rendering object ids, `None`
+    /// for no such target. See [`Self::on_render_picking`].
+    pub picking_format: Option<wgpu::TextureFormat>,
+    /// Records rendering into the target of [`Self::picking_format`].
+    ///
+    /// Runs right after [`Self::on_render`], into the same encoder. To find what
+    /// was clicked, call [`PickingTarget::read_pixel`] at the start of the hook,
+    /// which reads what the previous frame rendered.
+    #[allow(clippy::type_complexity)]
+    pub on_render_picking: Option<
+        Box<dyn FnMut(&wgpu::Device, &wgpu::Queue, &mut wgpu::CommandEncoder, &PickingTarget)>,
+    >,
+    /// Called once the GPU is set up, before `App::setup`.
+    ///
+    /// Use it to register native textures with
+    /// `RenderPass::egui_texture_from_wgpu_texture` and show them with `ui.image`.
+    pub on_gpu_setup: Option<Box<dyn FnOnce(&wgpu::Device, &wgpu::Queue, &mut RenderPass)>>,
+    /// Called with the frame captured after the app called
+    /// [`WindowHandle::request_screenshot`].
+    ///
+    /// The frame is rendered a second time for this, including [`Self::on_render`],
+    /// since wgpu can't copy from swap chain images. Only 8-bit RGBA and BGRA
+    /// [`Self::output_format`]s are supported.
+    pub on_screenshot: Option<Box<dyn FnMut(image::RgbaImage)>>,
+    /// Called every frame with the meshes egui tessellated, before they are
+    /// uploaded, e.g. to record or export the UI geometry.
+    pub on_tessellated: Option<Box<dyn FnMut(&[egui::ClippedMesh], &ScreenDescriptor)>>,
+    /// Called after `App::update` with each event egui reported about widget
+    /// interactions (focus, clicks, edited values), e.g. to hand them to a
+    /// screen reader or text-to-speech engine.
+    pub on_accessibility_event: Option<Box<dyn FnMut(&egui::OutputEvent)>>,
+    /// Called with every event before anything else handles it. Returning `true`
+    /// keeps egui from seeing the event; the integration still handles it
+    /// (resizing, closing, ...). This includes `Event::DeviceEvent`s.
+    pub on_event: Option<Box<dyn FnMut(&winit::event::Event<UserEvent<T>>) -> bool>>,
+    /// Called with every raw device event (unaccelerated mouse motion, keys by
+    /// scancode, ...), whether or not the window or egui has focus, e.g. for
+    /// first-person camera controls or global hotkeys. Not redrawn afterwards.
+    pub on_device_event: Option<Box<dyn FnMut(winit::event::DeviceId, &winit::event::DeviceEvent)>>,
+    /// Called with every [`UserEvent::Custom`], after which the window is redrawn.
+    pub on_user_event: Option<Box<dyn FnMut(T)>>,
+    /// Called when the GPU device was lost (e.g. after a driver crash) right
+    /// before the integration recreates it. The app and egui state survive,
+    /// but textures registered in [`Self::on_gpu_setup`] don't.
+    pub on_device_lost: Option<Box<dyn FnMut()>>,
+    /// Called every frame before `App::update`, e.g. to register native textures
+    /// that change each frame (video, thumbnails) and free the ones they replace
+    /// with `epi::TextureAllocator::free`, so they don't leak GPU memory.
+    pub on_update_textures: Option<Box<dyn FnMut(&wgpu::Device, &wgpu::Queue, &mut RenderPass)>>,
+    /// Called with GPU errors no error scope caught, e.g. validation errors,
+    /// instead of wgpu's default of panicking. Installed again on devices
+    /// recreated after [`Self::on_device_lost`].
+    pub on_uncaptured_error: Option<std::sync::Arc<dyn Fn(wgpu::Error) + Send + Sync>>,
+    /// Called once the egui context exists, before `App::setup`.
+    pub on_context_ready: Option<Box<dyn FnOnce(&egui::CtxRef)>>,
+}
+
+impl<T: 'static> Default for RunOptions<T> {
+    fn default() -> Self {
+        Self {
+            initial_size: None,
+            initial_size_points: None,
+            initial_pos: None,
+            center_on_start: false,
+            min_size: None,
+            min_size_points: Some(egui::vec2(200.0, 100.0)),
+            max_size: None,
+            aspect_ratio: None,
+            decorated: true,
+            resizable: true,
+            maximized: false,
+            always_on_top: false,
+            fullscreen: None,
+            fullscreen_toggle_key: None,
+            quit_shortcut: KeyboardShortcut::platform_quit(),
+            warmup_frames: 0,
+            start_hidden: false,
+            gpu: GpuOptions::default(),
+            shared_gpu: None,
+            present_mode: PresentMode::Mailbox,
+            clear_color: Some(egui::Rgba::BLACK),
+            transparent: false,
+            swap_chain_usage: TextureUsage::RENDER_ATTACHMENT,
+            render_scale: 1.0,
+            output_format: None,
+            icon: None,
+            app_id: None,
+            #[cfg(feature = "persistence")]
+            window_settings_path: None,
+            #[cfg(feature = "persistence")]
+            persist_egui_memory: true,
+            on_file_drop: None,
+            max_fps: None,
+            continuous: false,
+            unfocused_fps: None,
+            render_while_minimized: false,
+            idle_timeout: None,
+            on_idle: None,
+            web_info: None,
+            on_frame_history: None,
+            on_frame_timings: None,
+            time_source: None,
+            font_definitions: Default::default(),
+            extra_fonts: Vec::new(),
+            default_text_size: None,
+            style: Default::default(),
+            follow_system_theme: false,
+            window_handle: WindowHandle::default(),
+            zoom_factor: ZoomFactor::default(),
+            reported_scale: ReportedScale::Display,
+            force_scale_factor: None,
+            debug_label: "egui".to_owned(),
+            on_render: None,
+            picking_format: None,
+            on_render_picking: None,
+            on_gpu_setup: None,
+            on_update_textures: None,
+            on_context_ready: None,
+            on_uncaptured_error: None,
+            on_tessellated: None,
+            on_accessibility_event: None,
+            on_screenshot: None,
+            on_device_lost: None,
+            on_event: None,
+            on_device_event: None,
+            on_user_event: None,
+        }
+    }
+}
+
+fn to_wgpu_color(color: egui::Rgba) -> wgpu::Color {
+    wgpu::Color {
+        r: color.r() as f64,
+        g: color.g() as f64,
+        b: color.b() as f64,
+        a: color.a() as f64,
+    }
+}
+
+fn load_icon(icon: IconData) -> Option<winit::window::Icon> {
+    match winit::window::Icon::from_rgba(icon.rgba, icon.width, icon.height) {
+        Ok(icon) => Some(icon),
+        Err(e) => {
+            log::warn!("Failed to load window icon: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(feature = "persistence")]
+fn save_window_settings(path: &Option<std::path::PathBuf>, window_settings: &WindowSettings) {
+    if let Some(path) = path {
+        if let Err(e) = persistence::write_json(path, window_settings) {
+            log::error!("Failed to save window settings: {}", e);
+        }
+    }
+}
+
+/// Make the platform lay egui out at `scale_factor` physical pixels per point.
+///
+/// The platform has no setter for this, so it's told the window's scale factor changed.
+fn set_platform_scale_factor(
+    platform: &mut Platform,
+    window: &winit::window::Window,
+    scale_factor: f64,
+) {
+    let mut size = window.inner_size();
+    platform.handle_event::<()>(&winit::event::Event::WindowEvent {
+        window_id: window.id(),
+        event: WindowEvent::ScaleFactorChanged {
+            scale_factor,
+            new_inner_size: &mut size,
         },
-        None,
-    ))
-    .unwrap();
+    });
+}
 
+/// Number of frames in a row the swap chain has to be lost before the whole
+/// device is considered lost.
+const DEVICE_LOST_FRAMES: u32 = 3;
+
+fn request_adapter(
+    instance: &Instance,
+    gpu: &GpuOptions,
+    compatible_surface: Option<&wgpu::Surface>,
+) -> Result<wgpu::Adapter, RunError> {
+    block_on(request_adapter_async(instance, gpu, compatible_surface))
+}
+
+async fn request_adapter_async(
+    instance: &Instance,
+    gpu: &GpuOptions,
+    compatible_surface: Option<&wgpu::Surface>,
+) -> Result<wgpu::Adapter, RunError> {
+    let adapter = instance
+        .request_adapter(&RequestAdapterOptions {
+            power_preference: gpu.power_preference,
+            compatible_surface,
+        })
+        .await;
+    if let Some(adapter) = adapter {
+        return Ok(adapter);
+    }
+    if !gpu.allow_software_fallback {
+        return Err(RunError::NoAdapter);
+    }
+    let mut adapters: Vec<_> = instance.enumerate_adapters(gpu.backends).collect();
+    let software = adapters
+        .iter()
+        .position(|adapter| adapter.get_info().device_type == wgpu::DeviceType::Cpu);
+    let adapter = match software {
+        Some(index) => adapters.swap_remove(index),
+        None => adapters.pop().ok_or(RunError::NoAdapter)?,
+    };
+    log::warn!("Falling back to adapter {:?}", adapter.get_info());
+    Ok(adapter)
+}
+
+fn request_device(
+    adapter: &wgpu::Adapter,
+    features: Features,
+    limits: Limits,
+) -> Result<(wgpu::Device, wgpu::Queue), RunError> {
+    block_on(request_device_async(adapter, features, limits))
+}
+
+async fn request_device_async(
+    adapter: &wgpu::Adapter,
+    features: Features,
+    limits: Limits,
+) -> Result<(wgpu::Device, wgpu::Queue), RunError> {
+    let missing_features = features - adapter.features();
+    if !missing_features.is_empty() {
+        return Err(RunError::UnsupportedFeatures(missing_features));
+    }
+    adapter
+        .request_device(
+            &DeviceDescriptor {
+                features,
+                limits,
+                label: None,
+            },
+            None,
+        )
+        .await
+        .map_err(RunError::DeviceRequest)
+}
+
+/// The GPU objects [`setup_gpu`] creates for a window.
+pub struct GpuContext {
+    pub surface: wgpu::Surface,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub instance: wgpu::Instance,
+}
+
+/// GPU objects created outside the integration, see [`RunOptions::shared_gpu`].
+pub enum SharedGpu {
+    /// Create the surface with this instance, then pick an adapter and create
+    /// a device as usual with [`GpuOptions`].
+    Instance(wgpu::Instance),
+    /// Render with this device. `adapter` must belong to `instance` and be
+    /// able to present to the window; wgpu 0.7 can't check that up front.
+    /// [`GpuOptions`] only applies when the device has to be recreated.
+    Device {
+        instance: wgpu::Instance,
+        adapter: wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+    },
+    /// Render with everything [`setup_gpu`] returned, including its surface,
+    /// which must have been created for the window the integration renders to,
+    /// e.g. with [`run_with_window`]. As for `Device`, [`GpuOptions`] only
+    /// applies when the device has to be recreated.
+    Context(GpuContext),
+}
+
+/// Set up the GPU for rendering to `window` without blocking.
+///
+/// [`run`] blocks on this, which panics inside an async runtime like Tokio. Apps
+/// running in one can await this instead and then drive an [`EguiRenderer`]
+/// from the (necessarily blocking) winit event loop, or hand the context to
+/// the integration's loop with [`SharedGpu::Context`]. Passing its parts as
+/// [`SharedGpu::Device`] instead creates another surface for the window, so
+/// drop `surface` first then.
+pub async fn setup_gpu(
+    window: &winit::window::Window,
+    gpu: &GpuOptions,
+) -> Result<GpuContext, RunError> {
+    setup_shared_gpu(
+        window,
+        gpu,
+        SharedGpu::Instance(Instance::new(gpu.backends)),
+    )
+    .await
+}
+
+async fn setup_shared_gpu(
+    window: &winit::window::Window,
+    gpu: &GpuOptions,
+    shared: SharedGpu,
+) -> Result<GpuContext, RunError> {
+    match shared {
+        SharedGpu::Instance(instance) => {
+            let surface = unsafe { instance.create_surface(window) };
+            let adapter = request_adapter_async(&instance, gpu, Some(&surface)).await?;
+            let (device, queue) =
+                request_device_async(&adapter, gpu.features, gpu.limits.clone()).await?;
+            Ok(GpuContext {
+                surface,
+                adapter,
+                device,
+                queue,
+                instance,
+            })
+        }
+        SharedGpu::Device {
+            instance,
+            adapter,
+            device,
+            queue,
+        } => {
+            let surface = unsafe { instance.create_surface(window) };
+            Ok(GpuContext {
+                surface,
+                adapter,
+                device,
+                queue,
+                instance,
+            })
+        }
+        SharedGpu::Context(context) => Ok(context),
+    }
+}
+
+fn scale_text_styles(
+    mut font_definitions: egui::FontDefinitions,
+    body_size: Option<f32>,
+) -> egui::FontDefinitions {
+    let current = font_definitions
+        .family_and_size
+        .get(&egui::TextStyle::Body)
+        .map(|&(_, size)| size);
+    if let (Some(body_size), Some(current)) = (body_size, current) {
+        let factor = body_size / current;
+        for (_, size) in font_definitions.family_and_size.values_mut() {
+            *size *= factor;
+        }
+    }
+    font_definitions
+}
+
+/// The requested swap chain format if surfaces can render to it, otherwise the
+/// surface's preferred one.
+///
+/// wgpu 0.7 can't query which usages an adapter supports for a format (that
+/// needs `TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES` and
+/// `Adapter::get_texture_format_features` from later versions), so the request
+/// is checked against the formats swap chains are guaranteed to support.
+fn choose_output_format(
+    requested: Option<wgpu::TextureFormat>,
+    preferred: wgpu::TextureFormat,
+) -> wgpu::TextureFormat {
+    match requested {
+        Some(format)
+            if matches!(
+                format,
+                wgpu::TextureFormat::Bgra8UnormSrgb
+                    | wgpu::TextureFormat::Bgra8Unorm
+                    | wgpu::TextureFormat::Rgba8UnormSrgb
+                    | wgpu::TextureFormat::Rgba8Unorm
+            ) =>
+        {
+            format
+        }
+        Some(format) => {
+            log::warn!(
+                "Swap chains can't use {:?}, using {:?} instead",
+                format,
+                preferred
+            );
+            preferred
+        }
+        None => preferred,
+    }
+}
+
+fn install_error_handler(
+    device: &wgpu::Device,
+    handler: &Option<std::sync::Arc<dyn Fn(wgpu::Error) + Send + Sync>>,
+) {
+    if let Some(handler) = handler.clone() {
+        device.on_uncaptured_error(move |error| handler(error));
+    }
+}
+
+/// Whether `event` comes from the user, as opposed to the window system.
+fn is_user_input(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::KeyboardInput { .. }
+            | WindowEvent::ReceivedCharacter(_)
+            | WindowEvent::CursorMoved { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::Touch(_)
+            | WindowEvent::DroppedFile(_)
+    )
+}
+
+/// The size closest to `size` with `width / height == aspect_ratio`, changing
+/// only one of them.
+fn fit_aspect_ratio(
+    size: winit::dpi::PhysicalSize<u32>,
+    aspect_ratio: f32,
+) -> winit::dpi::PhysicalSize<u32> {
+    let width = (size.height as f32 * aspect_ratio).round() as u32;
+    let height = (size.width as f32 / aspect_ratio).round() as u32;
+    if (width as i64 - size.width as i64).abs() <= (height as i64 - size.height as i64).abs() {
+        winit::dpi::PhysicalSize::new(width.max(1), size.height)
+    } else {
+        winit::dpi::PhysicalSize::new(size.width, height.max(1))
+    }
+}
+
+/// Size of the frame rendered for a `width` x `height` window, at least 1x1.
+fn scaled_size(width: u32, height: u32, render_scale: f32) -> (u32, u32) {
+    let scale = |size: u32| ((size as f32 * render_scale).round() as u32).max(1);
+    (scale(width), scale(height))
+}
+
+/// The OS theme, where winit can tell.
+fn system_theme(window: &winit::window::Window) -> Option<winit::window::Theme> {
+    #[cfg(windows)]
+    {
+        use winit::platform::windows::WindowExtWindows;
+        Some(window.theme())
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = window;
+        None
+    }
+}
+
+fn visuals_for(theme: winit::window::Theme) -> egui::Visuals {
+    match theme {
+        winit::window::Theme::Dark => egui::Visuals::dark(),
+        winit::window::Theme::Light => egui::Visuals::light(),
+    }
+}
+
+/// An app that overrides nothing, to tell epi's defaults apart.
+struct DefaultApp;
+
+impl epi::App for DefaultApp {
+    fn update(&mut self, _ctx: &egui::CtxRef, _frame: &mut epi::Frame<'_>) {}
+
+    fn name(&self) -> &str {
+        ""
+    }
+}
+
+/// `App::max_size_points` if the app overrides it with a finite size. epi's
+/// default is the size limit of web canvases, which would keep native windows
+/// from being maximized.
+fn app_max_size_points(app: &dyn epi::App) -> Option<egui::Vec2> {
+    let size = app.max_size_points();
+    let is_default = size == epi::App::max_size_points(&DefaultApp);
+    if is_default || !size.x.is_finite() || !size.y.is_finite() {
+        None
+    } else {
+        Some(size)
+    }
+}
+
+fn physical_size(size: egui::Vec2) -> winit::dpi::PhysicalSize<f32> {
+    winit::dpi::PhysicalSize::new(size.x, size.y)
+}
+
+/// Run the app, panicking if the window or GPU can't be set up.
+pub fn run(app: Box<dyn epi::App>) -> ! {
+    run_with_options(app, RunOptions::default())
+}
+
+/// An [`epi::App`] drawing its UI with a closure.
+struct SimpleApp<F> {
+    name: String,
+    update: F,
+}
+
+impl<F: FnMut(&egui::CtxRef)> epi::App for SimpleApp<F> {
+    fn update(&mut self, ctx: &egui::CtxRef, _frame: &mut epi::Frame<'_>) {
+        (self.update)(ctx)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Run a closure drawing the UI each frame, without implementing [`epi::App`].
+pub fn run_simple(window_title: &str, update: impl FnMut(&egui::CtxRef) + 'static) -> ! {
+    run(Box::new(SimpleApp {
+        name: window_title.to_owned(),
+        update,
+    }))
+}
+
+/// Like [`run`], but with the window configured by `options`.
+pub fn run_with_options(app: Box<dyn epi::App>, options: RunOptions) -> ! {
+    try_run_with_options(app, options).unwrap();
+    unreachable!("the event loop never returns")
+}
+
+/// Run the app. Only returns if setting up the window or GPU fails.
+///
+/// The app exits when it calls `Frame::quit` or the window is closed, after
+/// `App::on_exit` (and `App::save` with the `persistence` feature) ran.
+pub fn try_run(app: Box<dyn epi::App>) -> Result<(), RunError> {
+    try_run_with_options(app, RunOptions::default())
+}
+
+/// Like [`try_run`], but with the window configured by `options`.
+pub fn try_run_with_options(app: Box<dyn epi::App>, options: RunOptions) -> Result<(), RunError> {
+    try_run_with_event_loop(
+        app,
+        options,
+        winit::event_loop::EventLoop::with_user_event(),
+    )
+}
+
+/// Like [`run_with_options`], but on an event loop created by the caller.
+///
+/// Create it with `EventLoop::with_user_event()` and keep a proxy from
+/// `event_loop.create_proxy()` to send [`UserEvent::Custom`] events from other
+/// threads, e.g. to tell the UI that data finished loading.
+pub fn run_with_event_loop<T: Send + 'static>(
+    app: Box<dyn epi::App>,
+    options: RunOptions<T>,
+    event_loop: winit::event_loop::EventLoop<UserEvent<T>>,
+) -> ! {
+    try_run_with_event_loop(app, options, event_loop).unwrap();
+    unreachable!("the event loop never returns")
+}
+
+/// Like [`try_run_with_options`], but on an event loop created by the caller.
+/// See [`run_with_event_loop`].
+pub fn try_run_with_event_loop<T: Send + 'static>(
+    app: Box<dyn epi::App>,
+    mut options: RunOptions<T>,
+    event_loop: winit::event_loop::EventLoop<UserEvent<T>>,
+) -> Result<(), RunError> {
+    let window = build_window(app.as_ref(), &mut options, &event_loop)?;
+    try_run_with_window(app, options, event_loop, window)
+}
+
+/// Build and place the window as configured by `options`.
+fn build_window<T: 'static>(
+    app: &dyn epi::App,
+    options: &mut RunOptions<T>,
+    event_loop: &winit::event_loop::EventLoop<UserEvent<T>>,
+) -> Result<winit::window::Window, RunError> {
+    let name = app.name();
+    let mut window_builder = winit::window::WindowBuilder::new()
+        .with_title(name)
+        .with_decorations(options.decorated)
+        .with_resizable(options.resizable)
+        .with_maximized(options.maximized)
+        .with_transparent(options.transparent)
+        .with_always_on_top(options.always_on_top)
+        .with_visible(options.warmup_frames == 0 && !options.start_hidden)
+        .with_fullscreen(
+            options
+                .fullscreen
+                .and_then(|mode| mode.to_winit(event_loop.primary_monitor())),
+        )
+        .with_window_icon(options.icon.take().and_then(load_icon));
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    if let Some(app_id) = &options.app_id {
+        use winit::platform::unix::WindowBuilderExtUnix;
+        window_builder = window_builder
+            .with_app_id(app_id.clone())
+            .with_class(app_id.clone(), app_id.clone());
+    }
+    if let Some(size) = options.initial_size {
+        window_builder = window_builder.with_inner_size(physical_size(size));
+    }
+    // Sizes in points are converted here rather than by winit, which wouldn't
+    // know about `force_scale_factor`.
+    let scale_factor = options.force_scale_factor.unwrap_or_else(|| {
+        event_loop
+            .primary_monitor()
+            .map_or(1.0, |monitor| monitor.scale_factor())
+    }) as f32;
+    if let Some(points) = options.initial_size_points {
+        window_builder = window_builder.with_inner_size(physical_size(points * scale_factor));
+    }
+    if let Some(size) = options.min_size {
+        window_builder = window_builder.with_min_inner_size(physical_size(size));
+    } else if let Some(points) = options.min_size_points {
+        window_builder = window_builder.with_min_inner_size(physical_size(points * scale_factor));
+    }
+    if let Some(size) = options.max_size {
+        window_builder = window_builder.with_max_inner_size(physical_size(size));
+    } else if let Some(points) = app_max_size_points(app) {
+        window_builder = window_builder.with_max_inner_size(physical_size(points * scale_factor));
+    }
+    #[cfg(feature = "persistence")]
+    let window_settings = options
+        .window_settings_path
+        .as_ref()
+        .and_then(WindowSettings::from_json_file);
+    #[cfg(feature = "persistence")]
+    if let Some(window_settings) = &window_settings {
+        window_builder = window_settings.initialize_size(window_builder);
+    }
+    let window = window_builder
+        .build(event_loop)
+        .map_err(RunError::WindowBuild)?;
+    if options.always_on_top {
+        window_handle::warn_if_always_on_top_unsupported(&window);
+    }
+    if let Some(pos) = options.initial_pos {
+        let pos = winit::dpi::PhysicalPosition::new(pos.x, pos.y).cast::<i32>();
+        if monitor::is_on_screen(&window, pos) {
+            window.set_outer_position(pos);
+        } else if let Some(monitor) = window.primary_monitor() {
+            log::warn!("Initial window position {:?} is off-screen, centering", pos);
+            monitor::center_on(&window, &monitor);
+        }
+    } else if options.center_on_start {
+        match event_loop.primary_monitor() {
+            Some(monitor) => monitor::center_on(&window, &monitor),
+            None => log::debug!("No primary monitor reported, not centering the window"),
+        }
+    }
+    #[cfg(feature = "persistence")]
+    if let Some(window_settings) = &window_settings {
+        window_settings.restore_positions(&window);
+    }
+    Ok(window)
+}
+
+/// Like [`run_with_event_loop`], but rendering into a window created by the
+/// caller, e.g. to parent it into a host application.
+///
+/// The options applied when building a window (size, position, decorations,
+/// icon, ...) are ignored; `window` must belong to `event_loop`.
+pub fn run_with_window<T: Send + 'static>(
+    app: Box<dyn epi::App>,
+    options: RunOptions<T>,
+    event_loop: winit::event_loop::EventLoop<UserEvent<T>>,
+    window: winit::window::Window,
+) -> ! {
+    try_run_with_window(app, options, event_loop, window).unwrap();
+    unreachable!("the event loop never returns")
+}
+
+/// Like [`try_run_with_event_loop`], but rendering into a window created by the
+/// caller. See [`run_with_window`].
+pub fn try_run_with_window<T: Send + 'static>(
+    app: Box<dyn epi::App>,
+    options: RunOptions<T>,
+    event_loop: winit::event_loop::EventLoop<UserEvent<T>>,
+    window: winit::window::Window,
+) -> Result<(), RunError> {
+    let event_handler = event_handler(app, options, &event_loop, window)?;
+    event_loop.run(event_handler)
+}
+
+/// Render a single frame of `app` in a real window and return it, e.g. to
+/// generate previews of UI states in a build pipeline.
+///
+/// Unlike [`render_to_image`], this renders with the window's surface format
+/// and scale factor and all of `options`, then leaves the event loop after the
+/// frame. `options.on_screenshot` is replaced; set
+/// [`RunOptions::warmup_frames`] to let the layout settle first. winit doesn't
+/// support creating more than one event loop on every platform, so call this
+/// at most once per process.
+#[cfg(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+pub fn run_once_capture(app: Box<dyn epi::App>, options: RunOptions) -> image::RgbaImage {
+    try_run_once_capture(app, options).unwrap()
+}
+
+/// See [`run_once_capture`]. Fails with [`RunError::Readback`] if the surface
+/// format isn't 8-bit RGBA or BGRA, reading back the frame failed, or the
+/// window was closed before a frame was rendered.
+#[cfg(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+pub fn try_run_once_capture(
+    app: Box<dyn epi::App>,
+    mut options: RunOptions,
+) -> Result<image::RgbaImage, RunError> {
+    use winit::platform::run_return::EventLoopExtRunReturn;
+    let captured = std::rc::Rc::new(std::cell::RefCell::new(None));
+    let sink = captured.clone();
+    options.on_screenshot = Some(Box::new(move |image| *sink.borrow_mut() = Some(image)));
+    // The screenshot is read right after the frame is submitted, so once this
+    // is set there's nothing left to wait for, whether or not reading worked.
+    let rendered = std::rc::Rc::new(std::cell::Cell::new(false));
+    let mut on_frame_timings = options.on_frame_timings.take();
+    let rendered_sink = rendered.clone();
+    options.on_frame_timings = Some(Box::new(move |timings: &FrameTimings| {
+        rendered_sink.set(true);
+        if let Some(on_frame_timings) = &mut on_frame_timings {
+            on_frame_timings(timings);
+        }
+    }));
+    let window_handle = options.window_handle.clone();
+    window_handle.request_screenshot();
+    let mut event_loop = winit::event_loop::EventLoop::with_user_event();
+    let window = build_window(app.as_ref(), &mut options, &event_loop)?;
+    let mut event_handler = event_handler(app, options, &event_loop, window)?;
+    if !window_handle
+        .output_format()
+        .map_or(false, screenshot::ScreenshotTarget::supports)
+    {
+        return Err(RunError::Readback);
+    }
+    event_loop.run_return(|event, target, control_flow| {
+        event_handler(event, target, control_flow);
+        if rendered.get() {
+            *control_flow = ControlFlow::Exit;
+        }
+    });
+    let image = captured.borrow_mut().take();
+    image.ok_or(RunError::Readback)
+}
+
+type EventTarget<T> = winit::event_loop::EventLoopWindowTarget<UserEvent<T>>;
+
+/// Set up the GPU and the app for `window`, returning the closure handling the
+/// events of `event_loop` from then on.
+fn event_handler<T: Send + 'static>(
+    mut app: Box<dyn epi::App>,
+    options: RunOptions<T>,
+    event_loop: &winit::event_loop::EventLoop<UserEvent<T>>,
+    window: winit::window::Window,
+) -> Result<
+    impl FnMut(winit::event::Event<'_, UserEvent<T>>, &EventTarget<T>, &mut ControlFlow) + 'static,
+    RunError,
+> {
+    let max_size_points = app_max_size_points(app.as_ref())
+        .unwrap_or_else(|| egui::vec2(f32::INFINITY, f32::INFINITY));
+    // Only one of them applies, as when building the window.
+    let (min_size, min_size_points) = match options.min_size {
+        Some(size) => (size, egui::Vec2::ZERO),
+        None => (
+            egui::Vec2::ZERO,
+            options.min_size_points.unwrap_or(egui::Vec2::ZERO),
+        ),
+    };
+    #[cfg(feature = "persistence")]
+    let window_settings_path = options.window_settings_path;
+    #[cfg(feature = "persistence")]
+    let mut storage = window_settings_path
+        .as_ref()
+        .map(|path| persistence::FileStorage::from_path(persistence::app_storage_path(path)));
+    let gpu = options.gpu;
+    let GpuContext {
+        surface,
+        adapter,
+        mut device,
+        mut queue,
+        instance,
+    } = match options.shared_gpu {
+        Some(shared) => block_on(setup_shared_gpu(&window, &gpu, shared)),
+        None => block_on(setup_gpu(&window, &gpu)),
+    }?;
+    let window_handle = options.window_handle;
+    let adapter_info = adapter.get_info();
+    log::info!(
+        "Using {} ({:?}, {:?})",
+        adapter_info.name,
+        adapter_info.device_type,
+        adapter_info.backend
+    );
+    window_handle.set_adapter_info(adapter_info);
+    let follow_system_theme = options.follow_system_theme;
+    let theme = system_theme(&window);
+    window_handle.set_theme(theme);
+    let mut style = options.style;
+    if let (true, Some(theme)) = (follow_system_theme, theme) {
+        style.visuals = visuals_for(theme);
+    }
+    let on_uncaptured_error = options.on_uncaptured_error;
+    install_error_handler(&device, &on_uncaptured_error);
+
+    let output_format = choose_output_format(
+        options.output_format,
+        adapter.get_swap_chain_preferred_format(&surface),
+    );
+    window_handle.set_output_format(output_format);
     let size = window.inner_size();
     let mut sc_desc = SwapChainDescriptor {
-        usage: TextureUsage::RENDER_ATTACHMENT,
-        format: OUTPUT_FORMAT,
+        usage: options.swap_chain_usage | TextureUsage::RENDER_ATTACHMENT,
+        format: output_format,
         width: size.width,
         height: size.height,
-        present_mode: PresentMode::Mailbox,
+        present_mode: options.present_mode,
     };
     let mut swap_chain = device.create_swap_chain(&surface, &sc_desc);
 
     let repaint_signal = std::sync::Arc::new(WgpuRepaintSignal(std::sync::Mutex::new(
-        event_loop.create_proxy(),
+        RepaintHandle::new(event_loop),
     )));
 
-    let mut platform = Platform::new(PlatformDescriptor {
-        physical_width: size.width,
-        physical_height: size.height,
-        scale_factor: window.scale_factor(),
-        font_definitions: Default::default(),
-        style: Default::default(),
-    });
+    let zoom_factor = options.zoom_factor;
+    let force_scale_factor = options.force_scale_factor;
+    let reported_scale = options.reported_scale;
+    let mut platform_scale_factor =
+        force_scale_factor.unwrap_or_else(|| window.scale_factor()) * zoom_factor.get() as f64;
+    let mut renderer = EguiRenderer::new(
+        &device,
+        output_format,
+        PlatformDescriptor {
+            physical_width: size.width,
+            physical_height: size.height,
+            scale_factor: platform_scale_factor,
+            font_definitions: scale_text_styles(
+                fonts::add_fonts(options.font_definitions, options.extra_fonts),
+                options.default_text_size,
+            ),
+            style,
+        },
+    );
+    if let Some(on_gpu_setup) = options.on_gpu_setup {
+        on_gpu_setup(&device, &queue, renderer.render_pass_mut());
+    }
+    if let Some(on_context_ready) = options.on_context_ready {
+        on_context_ready(&renderer.context());
+    }
+    app.setup(&renderer.context());
+    #[cfg(feature = "persistence")]
+    let persist_egui_memory = options.persist_egui_memory;
+    #[cfg(feature = "persistence")]
+    if let Some(storage) = &storage {
+        if persist_egui_memory {
+            persistence::load_memory(&renderer.context(), storage);
+        }
+        app.load(storage);
+    }
+    #[cfg(feature = "persistence")]
+    let mut last_auto_save = Instant::now();
+    // Whether an `App::update` ran since the last save.
+    #[cfg(feature = "persistence")]
+    let mut unsaved = false;
     let mut previous_frame_time = None;
-    let mut egui_render_pass = RenderPass::new(&device, OUTPUT_FORMAT);
-    let start_time = Instant::now();
-    #[cfg(feature = "http")]
-    let http = std::sync::Arc::new(epi_http::EpiHttp {});
+    let time_source = options
+        .time_source
+        .unwrap_or_else(|| Box::new(time_source::RealTime::new()));
+    for _ in 0..options.warmup_frames {
+        let info = IntegrationInfo {
+            web_info: options.web_info.clone(),
+            cpu_usage: None,
+            seconds_since_midnight: Some(seconds_since_midnight()),
+            native_pixels_per_point: Some(reported_scale.pick(
+                platform_scale_factor / zoom_factor.get() as f64,
+                platform_scale_factor,
+            )),
+        };
+        renderer.update(
+            app.as_mut(),
+            time_source.now(),
+            info,
+            repaint_signal.clone(),
+        );
+    }
+    if !options.start_hidden {
+        window.set_visible(true);
+    }
+    let transparent = options.transparent;
+    let clear_color = options
+        .clear_color
+        .map(|color| {
+            if transparent {
+                egui::Rgba::TRANSPARENT
+            } else {
+                color
+            }
+        })
+        .map(to_wgpu_color);
+    let mut on_file_drop = options.on_file_drop;
+    let mut on_render = options.on_render;
+    let picking_format = options.picking_format;
+    let mut on_render_picking = options.on_render_picking;
+    let mut picking_target: Option<PickingTarget> = None;
+    let debug_label = options.debug_label;
+    // Formatted once, each frame's encoder only borrows it.
+    let encoder_label = format!("{} encoder", debug_label);
+    let mut on_tessellated = options.on_tessellated;
+    let mut on_accessibility_event = options.on_accessibility_event;
+    let mut on_update_textures = options.on_update_textures;
+    let mut on_screenshot = options.on_screenshot;
+    let mut on_event = options.on_event;
+    let mut on_user_event = options.on_user_event;
+    let mut on_device_event = options.on_device_event;
+    let mut on_device_lost = options.on_device_lost;
+    let mut lost_frames = 0;
+    let mut file_drop = FileDrop::default();
+    let mut on_frame_history = options.on_frame_history;
+    let mut on_frame_timings = options.on_frame_timings;
+    let web_info = options.web_info;
+    let fullscreen_mode = options.fullscreen.unwrap_or(FullscreenMode::Borderless);
+    let fullscreen_toggle_key = options.fullscreen_toggle_key;
+    let quit_shortcut = options.quit_shortcut;
+    let mut modifiers = winit::event::ModifiersState::empty();
+    let mut cursor_icon = egui::CursorIcon::Default;
+    let mut frame_history = FrameHistory::default();
+    let frame_time_for = |fps: Option<u32>| {
+        fps.filter(|&fps| fps > 0)
+            .map(|fps| std::time::Duration::from_secs_f64(1.0 / fps as f64))
+    };
+    let min_frame_time = frame_time_for(options.max_fps);
+    let refresh_rate_cap = options.max_fps.is_none();
+    let mut current_monitor = window.current_monitor();
+    window_handle.set_refresh_rate(current_monitor.as_ref().and_then(monitor::refresh_rate));
+    let min_unfocused_frame_time = frame_time_for(options.unfocused_fps).or(min_frame_time);
+    let mut focused = true;
+    let render_while_minimized = options.render_while_minimized;
+    let render_scale = options.render_scale;
+    let aspect_ratio = options.aspect_ratio.filter(|&ratio| ratio > 0.0);
+    // The size last requested to keep the aspect ratio.
+    let mut aspect_resize: Option<winit::dpi::PhysicalSize<u32>> = None;
+    let continuous = options.continuous;
+    let idle_timeout = options.idle_timeout;
+    let mut on_idle = options.on_idle;
+    let mut last_input = Instant::now();
+    let mut scaled_target: Option<scaled_target::ScaledTarget> = None;
+    // The geometry saved on exit if the window is minimized by then.
+    #[cfg(feature = "persistence")]
+    let mut last_window_settings = WindowSettings::from_display(&window).unwrap_or_default();
+    // Whether anything happened since the last frame that could change it.
+    let redraw_pending = std::cell::Cell::new(true);
 
-    event_loop.run(move |event, _, control_flow| {
-        platform.handle_event(&event);
+    let event_handler = move |event: winit::event::Event<'_, UserEvent<T>>,
+                              _: &EventTarget<T>,
+                              control_flow: &mut ControlFlow| {
+        let consumed = match &mut on_event {
+            Some(on_event) => on_event(&event),
+            None => false,
+        };
+        // Every event `on_event` didn't consume reaches egui first, the match below
+        // only adds the integration's own handling (swap chain, exit, redraw
+        // scheduling). This includes `ReceivedCharacter`, which is how text
+        // committed by an IME arrives; winit 0.24 has no separate composition events.
+        if !consumed {
+            renderer.handle_event(&event);
+            if let winit::event::Event::WindowEvent {
+                event: WindowEvent::ScaleFactorChanged { scale_factor, .. },
+                ..
+            } = &event
+            {
+                platform_scale_factor = *scale_factor;
+            }
+        }
+        if let winit::event::Event::WindowEvent { event, .. } = &event {
+            if is_user_input(event) {
+                last_input = Instant::now();
+            }
+        }
+        // Input, repaint requests and the deadlines set by the last frame
+        // (animations, `max_fps`, auto-save, idle timeout). Raw device events
+        // only count while the cursor is grabbed, see below.
+        if matches!(
+            event,
+            winit::event::Event::WindowEvent { .. }
+                | winit::event::Event::UserEvent(_)
+                | winit::event::Event::NewEvents(winit::event::StartCause::Poll)
+                | winit::event::Event::NewEvents(
+                    winit::event::StartCause::ResumeTimeReached { .. }
+                )
+        ) {
+            redraw_pending.set(true);
+        }
         let mut redraw = || {
-            platform.update_time(start_time.elapsed().as_secs_f64());
+            redraw_pending.set(false);
+            if let Some(idle_timeout) = idle_timeout {
+                if last_input.elapsed() >= idle_timeout {
+                    match &mut on_idle {
+                        Some(on_idle) => {
+                            on_idle();
+                            last_input = Instant::now();
+                        }
+                        None => {
+                            *control_flow = ControlFlow::Exit;
+                            return;
+                        }
+                    }
+                }
+            }
+            let native_scale_factor = force_scale_factor.unwrap_or_else(|| window.scale_factor());
+            let scale_factor = native_scale_factor * zoom_factor.get() as f64;
+            let reported_scale_factor = reported_scale.pick(native_scale_factor, scale_factor);
+            // Minimized windows are resized to 0x0 and have nothing to render to.
+            // Windows hidden behind others keep rendering: winit 0.24 doesn't
+            // report occlusion (`WindowEvent::Occluded` only exists in later
+            // versions), so there's nothing to pause on.
+            if sc_desc.width == 0 || sc_desc.height == 0 {
+                *control_flow = winit::event_loop::ControlFlow::Wait;
+                if render_while_minimized {
+                    let info = IntegrationInfo {
+                        web_info: web_info.clone(),
+                        cpu_usage: previous_frame_time,
+                        seconds_since_midnight: Some(seconds_since_midnight()),
+                        native_pixels_per_point: Some(reported_scale_factor),
+                    };
+                    let (egui_output, app_output) = renderer.update(
+                        app.as_mut(),
+                        time_source.now(),
+                        info,
+                        repaint_signal.clone(),
+                    );
+                    window_handle.apply(&window);
+                    if app_output.quit || window_handle.should_close() {
+                        *control_flow = winit::event_loop::ControlFlow::Exit;
+                    } else if egui_output.needs_repaint {
+                        // Redrawn on MainEventsCleared.
+                        *control_flow = match min_unfocused_frame_time {
+                            Some(min_frame_time) => winit::event_loop::ControlFlow::WaitUntil(
+                                Instant::now() + min_frame_time,
+                            ),
+                            None => winit::event_loop::ControlFlow::Poll,
+                        };
+                    }
+                }
+                return;
+            }
+            if scale_factor != platform_scale_factor {
+                set_platform_scale_factor(renderer.platform_mut(), &window, scale_factor);
+                platform_scale_factor = scale_factor;
+            }
+            if let Some(present_mode) = window_handle.take_present_mode() {
+                if present_mode != sc_desc.present_mode {
+                    log::debug!("Switching present mode to {:?}", present_mode);
+                    sc_desc.present_mode = present_mode;
+                    swap_chain = device.create_swap_chain(&surface, &sc_desc);
+                }
+            }
 
             let output_frame = match swap_chain.get_current_frame() {
-                Ok(frame) => frame,
+                Ok(frame) => {
+                    lost_frames = 0;
+                    frame
+                }
                 Err(e) => {
-                    eprintln!("Dropped frame with error: {}", e);
+                    dropped_frame::log(&e);
+                    if let wgpu::SwapChainError::Lost = e {
+                        lost_frames += 1;
+                    } else {
+                        lost_frames = 0;
+                    }
+                    if lost_frames >= DEVICE_LOST_FRAMES {
+                        log::warn!("GPU device lost, recreating it");
+                        if let Some(on_device_lost) = &mut on_device_lost {
+                            on_device_lost();
+                        }
+                        let recreated =
+                            request_adapter(&instance, &gpu, Some(&surface)).and_then(|adapter| {
+                                window_handle.set_adapter_info(adapter.get_info());
+                                request_device(&adapter, gpu.features, gpu.limits.clone())
+                            });
+                        match recreated {
+                            Ok((new_device, new_queue)) => {
+                                device = new_device;
+                                queue = new_queue;
+                                install_error_handler(&device, &on_uncaptured_error);
+                                renderer.recreate_render_pass(&device, output_format);
+                                scaled_target = None;
+                                picking_target = None;
+                                swap_chain = device.create_swap_chain(&surface, &sc_desc);
+                                lost_frames = 0;
+                            }
+                            // Try again on the next frame.
+                            Err(e) => log::error!("Failed to recreate GPU device: {}", e),
+                        }
+                        redraw_pending.set(true);
+                        window.request_redraw();
+                    } else if dropped_frame::needs_new_swap_chain(&e) {
+                        swap_chain = device.create_swap_chain(&surface, &sc_desc);
+                        redraw_pending.set(true);
+                        window.request_redraw();
+                    }
                     return;
                 }
             };
 
-            let pixel_pre_point = window.scale_factor();
             let frame_start = Instant::now();
-            platform.begin_frame();
-            let mut app_output = epi::backend::AppOutput::default();
-            let mut frame = epi::backend::FrameBuilder {
-                info: IntegrationInfo {
-                    web_info: None,
-                    cpu_usage: previous_frame_time,
-                    seconds_since_midnight: Some(seconds_since_midnight()),
-                    native_pixels_per_point: Some(pixel_pre_point as _),
-                },
-                tex_allocator: &mut egui_render_pass,
-                #[cfg(feature = "http")]
-                http: http.clone(),
-                output: &mut app_output,
-                repaint_signal: repaint_signal.clone(),
-            }
-            .build();
-            app.update(&platform.context(), &mut frame);
-            let (egui_output, shapes) = platform.end_frame();
-            let clipped_meshes = platform.context().tessellate(shapes);
+            if let Some(on_file_drop) = &mut on_file_drop {
+                if !file_drop.is_empty() {
+                    on_file_drop(&file_drop);
+                }
+            }
+            file_drop.dropped_files.clear();
+            if let Some(on_frame_history) = &mut on_frame_history {
+                on_frame_history(&frame_history);
+            }
+            if let Some(on_update_textures) = &mut on_update_textures {
+                on_update_textures(&device, &queue, renderer.render_pass_mut());
+            }
+            let info = IntegrationInfo {
+                web_info: web_info.clone(),
+                cpu_usage: previous_frame_time,
+                seconds_since_midnight: Some(seconds_since_midnight()),
+                native_pixels_per_point: Some(reported_scale_factor),
+            };
+            let (egui_output, app_output) = renderer.update(
+                app.as_mut(),
+                time_source.now(),
+                info,
+                repaint_signal.clone(),
+            );
+            window_handle.apply(&window);
+            if let Some(on_accessibility_event) = &mut on_accessibility_event {
+                for event in &egui_output.events {
+                    on_accessibility_event(event);
+                }
+            }
+            let screenshot = match &on_screenshot {
+                Some(_) if window_handle.take_screenshot_request() => {
+                    if screenshot::ScreenshotTarget::supports(output_format) {
+                        let (width, height) =
+                            scaled_size(sc_desc.width, sc_desc.height, render_scale);
+                        Some(screenshot::ScreenshotTarget::new(
+                            &device,
+                            output_format,
+                            width,
+                            height,
+                        ))
+                    } else {
+                        log::warn!("Can't take screenshots of {:?} frames", output_format);
+                        None
+                    }
+                }
+                _ => None,
+            };
+            #[cfg(feature = "persistence")]
+            if let Some(storage) = &mut storage {
+                if last_auto_save.elapsed() > app.auto_save_interval() {
+                    app.save(storage);
+                    storage.flush();
+                    last_auto_save = Instant::now();
+                    unsaved = false;
+                } else {
+                    unsaved = true;
+                }
+            }
+            if egui_output.cursor_icon != cursor_icon {
+                cursor_icon = egui_output.cursor_icon;
+                cursor::set_cursor_icon(&window, cursor_icon, window_handle.cursor_hidden());
+            }
             let frame_time = (Instant::now() - frame_start).as_secs_f32();
             previous_frame_time = Some(frame_time);
+            frame_history.push(frame_time);
             let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
-                label: Some("egui encoder"),
+                label: Some(&encoder_label),
             });
+            let (render_width, render_height) =
+                scaled_size(sc_desc.width, sc_desc.height, render_scale);
+            if render_scale != 1.0
+                && scaled_target.as_ref().map_or(true, |target| {
+                    (target.width, target.height) != (render_width, render_height)
+                })
+            {
+                scaled_target = Some(scaled_target::ScaledTarget::new(
+                    &device,
+                    output_format,
+                    render_width,
+                    render_height,
+                ));
+            }
+            let view = match &scaled_target {
+                Some(target) => &target.view,
+                _ => &output_frame.output.view,
+            };
+            // egui still lays out in points of the full window, so input maps as usual.
+            // The scale includes the zoom since meshes are in zoomed points, see
+            // `ZoomFactor` for how it relates to the display scale.
             let screen_descriptor = ScreenDescriptor {
-                physical_width: sc_desc.width,
-                physical_height: sc_desc.height,
-                scale_factor: window.scale_factor() as f32,
+                physical_width: render_width,
+                physical_height: render_height,
+                scale_factor: platform_scale_factor as f32 * render_scale,
             };
-            egui_render_pass.update_texture(&device, &queue, &platform.context().texture());
-            egui_render_pass.update_user_textures(&device, &queue);
-            egui_render_pass.update_buffers(&device, &queue, &clipped_meshes, &screen_descriptor);
-            egui_render_pass.execute(
+            if let Some(on_tessellated) = &mut on_tessellated {
+                on_tessellated(renderer.clipped_meshes(), &screen_descriptor);
+            }
+            if let Some(on_render) = &mut on_render {
+                on_render(&device, &queue, &mut encoder, view);
+            }
+            if let Some(format) = picking_format {
+                if picking_target.as_ref().map_or(true, |target| {
+                    target.size() != (sc_desc.width, sc_desc.height)
+                }) {
+                    picking_target = Some(PickingTarget::new(
+                        &device,
+                        format,
+                        sc_desc.width,
+                        sc_desc.height,
+                    ));
+                }
+                if let (Some(on_render_picking), Some(target)) =
+                    (&mut on_render_picking, &picking_target)
+                {
+                    on_render_picking(&device, &queue, &mut encoder, target);
+                }
+            }
+            encoder.push_debug_group(&debug_label);
+            renderer.render(
+                &device,
+                &queue,
                 &mut encoder,
-                &output_frame.output.view,
-                &clipped_meshes,
+                view,
                 &screen_descriptor,
-                Some(wgpu::Color::BLACK),
+                clear_color,
             );
+            encoder.pop_debug_group();
+            if let Some(target) = &scaled_target {
+                target.blit(&mut encoder, &output_frame.output.view);
+            }
+            if let Some(screenshot) = &screenshot {
+                if let Some(on_render) = &mut on_render {
+                    on_render(&device, &queue, &mut encoder, &screenshot.view);
+                }
+                renderer.render(
+                    &device,
+                    &queue,
+                    &mut encoder,
+                    &screenshot.view,
+                    &screen_descriptor,
+                    clear_color,
+                );
+                screenshot.copy(&mut encoder);
+            }
+            let submit_start = Instant::now();
             queue.submit(std::iter::once(encoder.finish()));
+            if let Some(on_frame_timings) = &mut on_frame_timings {
+                on_frame_timings(&FrameTimings {
+                    submit: submit_start.elapsed(),
+                    ..renderer.timings()
+                });
+            }
+            if let (Some(screenshot), Some(on_screenshot)) = (screenshot, &mut on_screenshot) {
+                match screenshot.read(&device) {
+                    Some(image) => on_screenshot(image),
+                    None => log::error!("Failed to read back the screenshot"),
+                }
+            }
             {
                 let AppOutput { quit, window_size } = app_output;
                 if let Some(window_size) = window_size {
-                    window.set_inner_size(
-                        winit::dpi::PhysicalSize {
-                            width: (platform.context().pixels_per_point() * window_size.x).round(),
-                            height: (platform.context().pixels_per_point() * window_size.y).round(),
-                        }
-                        .to_logical::<f32>(window.scale_factor()),
-                    );
+                    let window_size = window_size.min(max_size_points).max(min_size_points);
+                    let window_size =
+                        (renderer.context().pixels_per_point() * window_size).max(min_size);
+                    window.set_inner_size(winit::dpi::PhysicalSize {
+                        width: window_size.x.round(),
+                        height: window_size.y.round(),
+                    });
                 }
-                *control_flow = if quit {
+                // egui only tells whether it needs another frame, not when, so
+                // animations poll (or wait for `max_fps`) until they settle.
+                *control_flow = if quit || window_handle.should_close() {
                     winit::event_loop::ControlFlow::Exit
-                } else if egui_output.needs_repaint {
-                    window.request_redraw();
-                    winit::event_loop::ControlFlow::Poll
+                } else if egui_output.needs_repaint || continuous {
+                    let min_frame_time = if focused {
+                        min_frame_time
+                    } else {
+                        min_unfocused_frame_time
+                    }
+                    .or_else(|| {
+                        // Frames the display never shows only cost power.
+                        if refresh_rate_cap {
+                            frame_time_for(window_handle.refresh_rate())
+                        } else {
+                            None
+                        }
+                    });
+                    if let Some(min_frame_time) = min_frame_time {
+                        // The redraw is requested on MainEventsCleared once the wait is over.
+                        winit::event_loop::ControlFlow::WaitUntil(frame_start + min_frame_time)
+                    } else {
+                        window.request_redraw();
+                        winit::event_loop::ControlFlow::Poll
+                    }
                 } else {
                     winit::event_loop::ControlFlow::Wait
                 }
             }
+            // Wake up for the auto-save even if the app went idle right after a change.
+            #[cfg(feature = "persistence")]
+            if storage.is_some() && unsaved && *control_flow == ControlFlow::Wait {
+                *control_flow = ControlFlow::WaitUntil(last_auto_save + app.auto_save_interval());
+            }
+            if let Some(idle_timeout) = idle_timeout {
+                let deadline = last_input + idle_timeout;
+                match *control_flow {
+                    ControlFlow::Wait => *control_flow = ControlFlow::WaitUntil(deadline),
+                    ControlFlow::WaitUntil(instant) if instant > deadline => {
+                        *control_flow = ControlFlow::WaitUntil(deadline)
+                    }
+                    _ => (),
+                }
+            }
         };
 
         match event {
-            winit::event::Event::RedrawEventsCleared if cfg!(windows) => redraw(),
+            winit::event::Event::RedrawEventsCleared if cfg!(windows) => {
+                if redraw_pending.get() {
+                    redraw();
+                }
+            }
             winit::event::Event::RedrawRequested(_) if !cfg!(windows) => redraw(),
+            // The platform picks up the new scale factor itself.
             winit::event::Event::WindowEvent {
                 event: WindowEvent::Resized(size),
                 ..
+            }
+            | winit::event::Event::WindowEvent {
+                event:
+                    WindowEvent::ScaleFactorChanged {
+                        new_inner_size: &mut size,
+                        ..
+                    },
+                ..
             } => {
                 sc_desc.width = size.width;
                 sc_desc.height = size.height;
-                swap_chain = device.create_swap_chain(&surface, &sc_desc);
+                // Minimizing on Windows resizes to 0x0, which a swap chain can't have.
+                if size.width != 0 && size.height != 0 {
+                    #[cfg(feature = "persistence")]
+                    if let Some(window_settings) = WindowSettings::from_display(&window) {
+                        last_window_settings = window_settings;
+                    }
+                    // The resize following our own request is taken as is, even if the
+                    // window manager picked a different size, so the two can't fight.
+                    if let (Some(aspect_ratio), None) = (aspect_ratio, aspect_resize.take()) {
+                        let fitted = fit_aspect_ratio(size, aspect_ratio);
+                        if fitted != size {
+                            window.set_inner_size(fitted);
+                            aspect_resize = Some(fitted);
+                        }
+                    }
+                    swap_chain = device.create_swap_chain(&surface, &sc_desc);
+                }
             }
             winit::event::Event::WindowEvent {
-                event: WindowEvent::CloseRequested,
+                event: WindowEvent::ThemeChanged(theme),
                 ..
             } => {
+                window_handle.set_theme(Some(theme));
+                if follow_system_theme {
+                    let ctx = renderer.context();
+                    let mut style = (*ctx.style()).clone();
+                    style.visuals = visuals_for(theme);
+                    ctx.set_style(style);
+                }
+            }
+            // Dragged to another monitor, which may refresh at a different rate.
+            winit::event::Event::WindowEvent {
+                event: WindowEvent::Moved(_),
+                ..
+            } => {
+                #[cfg(feature = "persistence")]
+                if let Some(window_settings) = WindowSettings::from_display(&window) {
+                    last_window_settings = window_settings;
+                }
+                let monitor = window.current_monitor();
+                if monitor != current_monitor {
+                    window_handle
+                        .set_refresh_rate(monitor.as_ref().and_then(monitor::refresh_rate));
+                    current_monitor = monitor;
+                }
+            }
+            winit::event::Event::WindowEvent {
+                event: WindowEvent::HoveredFile(path),
+                ..
+            } => file_drop.hovered_files.push(path),
+            winit::event::Event::WindowEvent {
+                event: WindowEvent::HoveredFileCancelled,
+                ..
+            } => file_drop.hovered_files.clear(),
+            // winit sends one event per file, so several dropped files accumulate here.
+            winit::event::Event::WindowEvent {
+                event: WindowEvent::DroppedFile(path),
+                ..
+            } => {
+                file_drop.hovered_files.clear();
+                file_drop.dropped_files.push(path);
+            }
+            // The resize that follows recreates the swap chain at the new resolution.
+            winit::event::Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            winit::event::KeyboardInput {
+                                state: winit::event::ElementState::Pressed,
+                                virtual_keycode: Some(key),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } if Some(key) == fullscreen_toggle_key => {
+                if window.fullscreen().is_some() {
+                    window.set_fullscreen(None);
+                } else {
+                    window.set_fullscreen(fullscreen_mode.to_winit(window.current_monitor()));
+                }
+            }
+            winit::event::Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            winit::event::KeyboardInput {
+                                state: winit::event::ElementState::Pressed,
+                                virtual_keycode: Some(key),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } if Some(KeyboardShortcut { modifiers, key }) == quit_shortcut => {
                 *control_flow = ControlFlow::Exit;
             }
-            winit::event::Event::MainEventsCleared
-            | winit::event::Event::UserEvent(RequestRepaintEvent) => window.request_redraw(),
+            winit::event::Event::WindowEvent {
+                event: WindowEvent::ModifiersChanged(state),
+                ..
+            } => modifiers = state,
+            // Snap back to the full frame rate right away.
+            winit::event::Event::WindowEvent {
+                event: WindowEvent::Focused(is_focused),
+                ..
+            } => {
+                focused = is_focused;
+                window.request_redraw();
+            }
+            winit::event::Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => {
+                if window_handle.try_close() {
+                    *control_flow = ControlFlow::Exit;
+                } else {
+                    // Let the app show its confirmation.
+                    window.request_redraw();
+                }
+            }
+            // Emitted exactly once, however many exit triggers fired.
+            winit::event::Event::LoopDestroyed => {
+                app.on_exit();
+                #[cfg(feature = "persistence")]
+                if let Some(storage) = &mut storage {
+                    if persist_egui_memory {
+                        persistence::save_memory(&renderer.context(), storage);
+                    }
+                    app.save(storage);
+                    storage.flush();
+                }
+                // A window minimized at exit is saved as it was before.
+                #[cfg(feature = "persistence")]
+                save_window_settings(
+                    &window_settings_path,
+                    &WindowSettings::from_display(&window).unwrap_or(last_window_settings),
+                );
+            }
+            winit::event::Event::DeviceEvent { device_id, event } => {
+                if let Some(on_device_event) = &mut on_device_event {
+                    on_device_event(device_id, &event);
+                }
+                if let winit::event::DeviceEvent::MouseMotion { delta } = event {
+                    window_handle.add_mouse_delta(delta);
+                    if window_handle.cursor_grabbed() {
+                        redraw_pending.set(true);
+                    }
+                }
+            }
+            winit::event::Event::UserEvent(UserEvent::Custom(user_event)) => {
+                if let Some(on_user_event) = &mut on_user_event {
+                    on_user_event(user_event);
+                }
+                window.request_redraw();
+            }
+            // Waking up for nothing (e.g. raw mouse motion while the window is in the
+            // background) doesn't cost a frame.
+            winit::event::Event::MainEventsCleared => {
+                if redraw_pending.get() {
+                    window.request_redraw();
+                }
+            }
             _ => (),
         }
-    });
+    };
+    Ok(event_handler)
 }
 /// Time of day as seconds since midnight. Used for clock in demo app.
 pub fn seconds_since_midnight() -> f64 {
-    let time = chrono::Local::now().time();
+    seconds_since_midnight_from(chrono::Local::now().time())
+}
+
+/// Like [`seconds_since_midnight`], but in UTC rather than the local timezone.
+pub fn seconds_since_midnight_utc() -> f64 {
+    seconds_since_midnight_from(chrono::Utc::now().time())
+}
+
+/// Seconds since midnight of the given time of day.
+pub fn seconds_since_midnight_from(time: chrono::NaiveTime) -> f64 {
     time.num_seconds_from_midnight() as f64 + 1e-9 * (time.nanosecond() as f64)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seconds_since_midnight_of_fixed_times() {
+        let time = chrono::NaiveTime::from_hms_milli_opt(21, 2, 3, 500).unwrap();
+        let seconds = seconds_since_midnight_from(time);
+        assert!(
+            (seconds - (21.0 * 3600.0 + 123.5)).abs() < 1e-6,
+            "{}",
+            seconds
+        );
+    }
+
+    #[test]
+    fn seconds_since_midnight_utc_is_now() {
+        let seconds = seconds_since_midnight_utc();
+        let expected = seconds_since_midnight_from(chrono::Utc::now().time());
+        // Either close together or on both sides of midnight.
+        let difference = (expected - seconds).abs();
+        assert!(
+            difference < 1.0 || difference > 86399.0,
+            "{} vs {}",
+            seconds,
+            expected
+        );
+    }
+
+    #[test]
+    fn fit_aspect_ratio_changes_the_closer_side() {
+        use winit::dpi::PhysicalSize;
+        assert_eq!(
+            fit_aspect_ratio(PhysicalSize::new(1000, 500), 2.0),
+            PhysicalSize::new(1000, 500)
+        );
+        // Only the side that needs the smaller change is adjusted.
+        assert_eq!(
+            fit_aspect_ratio(PhysicalSize::new(1000, 600), 2.0),
+            PhysicalSize::new(1000, 500)
+        );
+        assert_eq!(
+            fit_aspect_ratio(PhysicalSize::new(400, 1000), 0.5),
+            PhysicalSize::new(500, 1000)
+        );
+        assert_eq!(
+            fit_aspect_ratio(PhysicalSize::new(1, 1), 0.25),
+            PhysicalSize::new(1, 1)
+        );
+    }
+
+    #[test]
+    fn scaled_size_rounds_and_stays_positive() {
+        assert_eq!(scaled_size(800, 600, 1.0), (800, 600));
+        assert_eq!(scaled_size(801, 601, 0.5), (401, 301));
+        assert_eq!(scaled_size(800, 600, 1.5), (1200, 900));
+        assert_eq!(scaled_size(1, 1, 0.1), (1, 1));
+    }
+
+    #[test]
+    fn choose_output_format_falls_back_to_preferred() {
+        use wgpu::TextureFormat::*;
+        assert_eq!(choose_output_format(None, Bgra8UnormSrgb), Bgra8UnormSrgb);
+        assert_eq!(
+            choose_output_format(Some(Rgba8Unorm), Bgra8UnormSrgb),
+            Rgba8Unorm
+        );
+        assert_eq!(
+            choose_output_format(Some(Rgba16Float), Bgra8UnormSrgb),
+            Bgra8UnormSrgb
+        );
+        assert_eq!(
+            choose_output_format(Some(Depth32Float), Bgra8Unorm),
+            Bgra8Unorm
+        );
+    }
+
+    #[test]
+    fn scale_text_styles_keeps_proportions() {
+        let size_of = |font_definitions: &egui::FontDefinitions, style| {
+            font_definitions.family_and_size[&style].1
+        };
+        let defaults = egui::FontDefinitions::default();
+        let body = size_of(&defaults, egui::TextStyle::Body);
+        let heading = size_of(&defaults, egui::TextStyle::Heading);
+
+        let unchanged = scale_text_styles(egui::FontDefinitions::default(), None);
+        assert_eq!(size_of(&unchanged, egui::TextStyle::Body), body);
+
+        let scaled = scale_text_styles(egui::FontDefinitions::default(), Some(body * 2.0));
+        assert!((size_of(&scaled, egui::TextStyle::Body) - body * 2.0).abs() < 1e-4);
+        assert!((size_of(&scaled, egui::TextStyle::Heading) - heading * 2.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,40 @@
+//! Placing the window relative to the monitors it can be shown on.
+use winit::dpi::PhysicalPosition;
+use winit::monitor::MonitorHandle;
+use winit::window::Window;
+
+/// Whether `pos` lies on any connected monitor.
+pub(crate) fn is_on_screen(window: &Window, pos: PhysicalPosition<i32>) -> bool {
+    window.available_monitors().any(|monitor| {
+        let origin = monitor.position();
+        let size = monitor.size();
+        pos.x >= origin.x
+            && pos.y >= origin.y
+            && pos.x < origin.x + size.width as i32
+            && pos.y < origin.y + size.height as i32
+    })
+}
+
+/// Move the window to the middle of `monitor`.
+pub(crate) fn center_on(window: &Window, monitor: &MonitorHandle) {
+    let origin = monitor.position();
+    let monitor_size = monitor.size();
+    let window_size = window.outer_size();
+    window.set_outer_position(PhysicalPosition {
+        x: origin.x + (monitor_size.width as i32 - window_size.width as i32) / 2,
+        y: origin.y + (monitor_size.height as i32 - window_size.height as i32) / 2,
+    });
+}
+
+/// Refresh rate of `monitor` in Hz. winit can't tell which video mode is
+/// active, so this is the highest rate among the modes at the monitor's
+/// current resolution.
+pub(crate) fn refresh_rate(monitor: &MonitorHandle) -> Option<u32> {
+    let size = monitor.size();
+    monitor
+        .video_modes()
+        .filter(|mode| mode.size() == size)
+        .map(|mode| mode.refresh_rate() as u32)
+        .filter(|&refresh_rate| refresh_rate > 0)
+        .max()
+}
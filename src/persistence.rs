@@ -0,0 +1,98 @@
+//! Reading and writing state as JSON files.
+use egui_wgpu_backend::epi;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Read a value from a JSON file. Returns `None` if the file is missing or malformed.
+pub fn read_json<T: serde::de::DeserializeOwned>(path: impl AsRef<Path>) -> Option<T> {
+    let file = std::fs::File::open(path).ok()?;
+    match serde_json::from_reader(std::io::BufReader::new(file)) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            log::warn!("Failed to parse JSON: {}", e);
+            None
+        }
+    }
+}
+
+/// Write a value to a JSON file, creating parent directories as needed.
+pub fn write_json<T: serde::Serialize>(path: impl AsRef<Path>, value: &T) -> std::io::Result<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    serde_json::to_writer_pretty(writer, value)?;
+    Ok(())
+}
+
+/// Storage key of egui's memory.
+const EGUI_MEMORY_KEY: &str = "egui";
+
+/// Restore widget state (open collapsing headers, window positions, ...) saved
+/// by [`save_memory`].
+pub fn load_memory(ctx: &epi::egui::CtxRef, storage: &dyn epi::Storage) {
+    if let Some(json) = storage.get_string(EGUI_MEMORY_KEY) {
+        match serde_json::from_str(&json) {
+            Ok(memory) => *ctx.memory() = memory,
+            Err(e) => log::warn!("Failed to restore egui memory: {}", e),
+        }
+    }
+}
+
+pub fn save_memory(ctx: &epi::egui::CtxRef, storage: &mut dyn epi::Storage) {
+    match serde_json::to_string(&*ctx.memory()) {
+        Ok(json) => storage.set_string(EGUI_MEMORY_KEY, json),
+        Err(e) => log::warn!("Failed to save egui memory: {}", e),
+    }
+}
+
+/// Where the app storage belonging to `window_settings_path` is kept:
+/// `window.json` keeps it in `window.app.json`, so the two never share a file.
+pub(crate) fn app_storage_path(window_settings_path: &Path) -> PathBuf {
+    let stem = window_settings_path
+        .file_stem()
+        .map_or_else(|| "window".into(), |stem| stem.to_string_lossy());
+    window_settings_path.with_file_name(format!("{}.app.json", stem))
+}
+
+/// [`epi::Storage`] backed by a JSON file.
+pub struct FileStorage {
+    path: PathBuf,
+    kv: HashMap<String, String>,
+    dirty: bool,
+}
+
+impl FileStorage {
+    /// Load the storage from `path`, starting empty if the file doesn't exist yet.
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            kv: read_json(&path).unwrap_or_default(),
+            path,
+            dirty: false,
+        }
+    }
+}
+
+impl epi::Storage for FileStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.kv.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        if self.kv.get(key) != Some(&value) {
+            self.kv.insert(key.to_owned(), value);
+            self.dirty = true;
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.dirty {
+            match write_json(&self.path, &self.kv) {
+                Ok(()) => self.dirty = false,
+                Err(e) => log::error!("Failed to save app storage: {}", e),
+            }
+        }
+    }
+}
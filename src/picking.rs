@@ -0,0 +1,131 @@
+use crate::wgpu;
+use futures_lite::future::block_on;
+
+/// An offscreen attachment the size of the window for rendering object ids
+/// next to the frame, e.g. an `R32Uint` target for click-to-select.
+///
+/// Render into [`Self::view`] from [`crate::RunOptions::on_render_picking`] and
+/// look up what's under the cursor with [`Self::read_pixel`].
+pub struct PickingTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+}
+
+impl PickingTarget {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("egui picking target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture,
+            view,
+            format,
+            width,
+            height,
+        }
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    /// Size in physical pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The raw bytes of the pixel at `x`, `y` (physical pixels from the top
+    /// left), e.g. 4 little-endian bytes of an `R32Uint` id.
+    ///
+    /// Blocks until the GPU finished everything submitted so far. `None` if the
+    /// pixel is outside the target, the format has no fixed size per pixel, or
+    /// reading failed.
+    pub fn read_pixel(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        x: u32,
+        y: u32,
+    ) -> Option<Vec<u8>> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let bytes_per_pixel = bytes_per_pixel(self.format)?;
+        // Even a single row of a texture-to-buffer copy must be padded to 256 bytes.
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("egui picking readback buffer"),
+            size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("egui picking readback encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+            },
+            wgpu::BufferCopyView {
+                buffer: &buffer,
+                layout: wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT,
+                    rows_per_image: 1,
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+        block_on(mapping).ok()?;
+        let pixel = slice.get_mapped_range()[..bytes_per_pixel].to_vec();
+        buffer.unmap();
+        Some(pixel)
+    }
+}
+
+fn bytes_per_pixel(format: wgpu::TextureFormat) -> Option<usize> {
+    use wgpu::TextureFormat::*;
+    Some(match format {
+        R8Unorm | R8Snorm | R8Uint | R8Sint => 1,
+        R16Uint | R16Sint | R16Float | Rg8Unorm | Rg8Snorm | Rg8Uint | Rg8Sint => 2,
+        R32Uint | R32Sint | R32Float | Rg16Uint | Rg16Sint | Rg16Float | Rgba8Unorm
+        | Rgba8UnormSrgb | Rgba8Snorm | Rgba8Uint | Rgba8Sint | Bgra8Unorm | Bgra8UnormSrgb
+        | Rgb10a2Unorm | Rg11b10Float => 4,
+        Rg32Uint | Rg32Sint | Rg32Float | Rgba16Uint | Rgba16Sint | Rgba16Float => 8,
+        Rgba32Uint | Rgba32Sint | Rgba32Float => 16,
+        _ => return None,
+    })
+}
@@ -0,0 +1,75 @@
+//! Copying rendered RGBA textures back to the CPU.
+use crate::wgpu;
+use futures_lite::future::block_on;
+
+/// A buffer a `width` x `height` RGBA texture is copied into and read from.
+pub(crate) struct Readback {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+}
+
+impl Readback {
+    pub(crate) fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        // Rows of a texture-to-buffer copy must start at multiples of 256 bytes.
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = (width * 4 + align - 1) / align * align;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("egui readback buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+        }
+    }
+
+    /// Record copying `texture`, which must have `COPY_SRC` usage, into the buffer.
+    pub(crate) fn copy_from(&self, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) {
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::BufferCopyView {
+                buffer: &self.buffer,
+                layout: wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row: self.padded_bytes_per_row,
+                    rows_per_image: self.height,
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth: 1,
+            },
+        );
+    }
+
+    /// Wait for the copy to finish and return the pixels without row padding.
+    /// Must be called after the commands recorded by [`Self::copy_from`] were submitted.
+    pub(crate) fn read(self, device: &wgpu::Device) -> Option<image::RgbaImage> {
+        let slice = self.buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+        block_on(mapping).ok()?;
+
+        let bytes_per_row = (self.width * 4) as usize;
+        let mut pixels = Vec::with_capacity(bytes_per_row * self.height as usize);
+        {
+            let padded = slice.get_mapped_range();
+            for row in padded.chunks(self.padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..bytes_per_row]);
+            }
+        }
+        self.buffer.unmap();
+        image::RgbaImage::from_raw(self.width, self.height, pixels)
+    }
+}
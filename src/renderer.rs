@@ -0,0 +1,162 @@
+use crate::{egui, epi, wgpu, FrameTimings, RenderPass, ScreenDescriptor};
+use egui_wgpu_backend::epi::backend::{AppOutput, FrameBuilder};
+use egui_wgpu_backend::epi::IntegrationInfo;
+use egui_winit_platform::{Platform, PlatformDescriptor};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Texture size every wgpu adapter supports. egui 0.10 can't be told the real
+/// limit (its `RawInput` has no `max_texture_side`) and wgpu 0.7's `Limits`
+/// don't report it, so the font atlas is only checked against this.
+const MAX_TEXTURE_SIDE: u32 = 8192;
+
+/// The per-frame work of the integration, without the window, device or event
+/// loop, for embedding egui into an application that owns those itself.
+///
+/// Pass it the window's events with [`Self::handle_event`], run the app with
+/// [`Self::update`] and record the egui pass into your own encoder with
+/// [`Self::render`]. [`crate::run`] is a thin wrapper around it.
+pub struct EguiRenderer {
+    platform: Platform,
+    render_pass: RenderPass,
+    clipped_meshes: Vec<egui::ClippedMesh>,
+    /// Whether the font atlas was reported as larger than `MAX_TEXTURE_SIDE`.
+    warned_texture_size: bool,
+    timings: FrameTimings,
+    #[cfg(feature = "http")]
+    http: Arc<epi_http::EpiHttp>,
+}
+
+impl EguiRenderer {
+    /// `output_format` is the format of the views passed to [`Self::render`].
+    pub fn new(
+        device: &wgpu::Device,
+        output_format: wgpu::TextureFormat,
+        descriptor: PlatformDescriptor,
+    ) -> Self {
+        Self {
+            platform: Platform::new(descriptor),
+            render_pass: RenderPass::new(device, output_format),
+            clipped_meshes: Vec::new(),
+            warned_texture_size: false,
+            timings: FrameTimings::default(),
+            #[cfg(feature = "http")]
+            http: Arc::new(epi_http::EpiHttp {}),
+        }
+    }
+
+    pub fn context(&self) -> egui::CtxRef {
+        self.platform.context()
+    }
+
+    pub fn platform_mut(&mut self) -> &mut Platform {
+        &mut self.platform
+    }
+
+    /// E.g. to register native textures with `RenderPass::egui_texture_from_wgpu_texture`.
+    pub fn render_pass_mut(&mut self) -> &mut RenderPass {
+        &mut self.render_pass
+    }
+
+    /// Durations of the last [`Self::update`] and [`Self::render`]. `submit` is
+    /// left to whoever submits the encoder.
+    pub fn timings(&self) -> FrameTimings {
+        self.timings
+    }
+
+    /// Replace the render pass, e.g. after the device was lost. Native textures
+    /// registered with the old one are gone.
+    pub fn recreate_render_pass(
+        &mut self,
+        device: &wgpu::Device,
+        output_format: wgpu::TextureFormat,
+    ) {
+        self.render_pass = RenderPass::new(device, output_format);
+    }
+
+    /// The meshes tessellated by the last [`Self::update`].
+    pub fn clipped_meshes(&self) -> &[egui::ClippedMesh] {
+        &self.clipped_meshes
+    }
+
+    /// Pass every event of the event loop, egui ignores those of other windows.
+    pub fn handle_event<T>(&mut self, event: &winit::event::Event<T>) {
+        self.platform.handle_event(event);
+    }
+
+    /// Run one frame of `app`, `seconds` after the start of the application.
+    ///
+    /// Returns what egui wants from the window (cursor icon, repaints, ...) and
+    /// what the app asked the integration for.
+    pub fn update(
+        &mut self,
+        app: &mut dyn epi::App,
+        seconds: f64,
+        info: IntegrationInfo,
+        repaint_signal: Arc<dyn epi::RepaintSignal>,
+    ) -> (egui::Output, AppOutput) {
+        let update_start = Instant::now();
+        self.platform.update_time(seconds);
+        self.platform.begin_frame();
+        let mut app_output = AppOutput::default();
+        let mut frame = FrameBuilder {
+            info,
+            tex_allocator: &mut self.render_pass,
+            #[cfg(feature = "http")]
+            http: self.http.clone(),
+            output: &mut app_output,
+            repaint_signal,
+        }
+        .build();
+        app.update(&self.platform.context(), &mut frame);
+        // With the `clipboard` feature, the platform copies `egui_output.copied_text`
+        // to the system clipboard here and pastes from it when handling Ctrl+V.
+        // With the `webbrowser` feature, it opens `egui_output.open_url` and logs
+        // if that fails.
+        let (egui_output, shapes) = self.platform.end_frame();
+        let tessellate_start = Instant::now();
+        self.timings.update = tessellate_start - update_start;
+        self.clipped_meshes = self.platform.context().tessellate(shapes);
+        self.timings.tessellate = tessellate_start.elapsed();
+        (egui_output, app_output)
+    }
+
+    /// Record the egui pass of the last [`Self::update`] into `encoder`.
+    ///
+    /// `clear_color` of `None` draws over the existing contents of `view`.
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        screen_descriptor: &ScreenDescriptor,
+        clear_color: Option<wgpu::Color>,
+    ) {
+        let upload_start = Instant::now();
+        let texture = self.platform.context().texture();
+        if !self.warned_texture_size && texture.width.max(texture.height) as u32 > MAX_TEXTURE_SIDE
+        {
+            self.warned_texture_size = true;
+            log::warn!(
+                "The egui font atlas is {}x{}, which may exceed what the GPU supports",
+                texture.width,
+                texture.height
+            );
+        }
+        // Only uploads if the atlas changed since the last call, which it does
+        // when new glyphs are needed.
+        self.render_pass.update_texture(device, queue, &texture);
+        self.render_pass.update_user_textures(device, queue);
+        self.render_pass
+            .update_buffers(device, queue, &self.clipped_meshes, screen_descriptor);
+        self.timings.upload = upload_start.elapsed();
+        self.render_pass.execute(
+            encoder,
+            view,
+            &self.clipped_meshes,
+            screen_descriptor,
+            clear_color,
+        );
+    }
+}
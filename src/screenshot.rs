@@ -0,0 +1,78 @@
+use crate::readback::Readback;
+use crate::wgpu;
+
+/// An offscreen copy of a frame, rendered next to the swap chain image.
+///
+/// Swap chain textures of wgpu 0.7 only expose a view, which can't be the source
+/// of a copy, so screenshots render the frame a second time into this target.
+///
+/// The target is always single-sampled, so it can be copied from. Should the
+/// egui pass ever render multisampled, it has to resolve into this target
+/// (as the `resolve_target`) instead of rendering into it directly.
+pub(crate) struct ScreenshotTarget {
+    texture: wgpu::Texture,
+    pub(crate) view: wgpu::TextureView,
+    bgra: bool,
+    readback: Readback,
+}
+
+impl ScreenshotTarget {
+    /// `format` must be one of those [`Self::supports`].
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("egui screenshot target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture,
+            view,
+            bgra: matches!(
+                format,
+                wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+            ),
+            readback: Readback::new(device, width, height),
+        }
+    }
+
+    /// Whether the pixels of frames in `format` can be returned as RGBA8.
+    pub(crate) fn supports(format: wgpu::TextureFormat) -> bool {
+        matches!(
+            format,
+            wgpu::TextureFormat::Bgra8Unorm
+                | wgpu::TextureFormat::Bgra8UnormSrgb
+                | wgpu::TextureFormat::Rgba8Unorm
+                | wgpu::TextureFormat::Rgba8UnormSrgb
+        )
+    }
+
+    /// Record copying the rendered frame into the readback buffer.
+    pub(crate) fn copy(&self, encoder: &mut wgpu::CommandEncoder) {
+        self.readback.copy_from(encoder, &self.texture);
+    }
+
+    /// Must be called after the commands recorded by [`Self::copy`] were submitted.
+    pub(crate) fn read(self, device: &wgpu::Device) -> Option<image::RgbaImage> {
+        let mut image = self.readback.read(device)?;
+        if self.bgra {
+            for pixel in image.pixels_mut() {
+                pixel.0.swap(0, 2);
+            }
+        }
+        Some(image)
+    }
+}
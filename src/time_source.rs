@@ -0,0 +1,46 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Where the integration takes egui's time from, which drives animations.
+pub trait TimeSource {
+    /// Seconds since the application started.
+    fn now(&self) -> f64;
+}
+
+/// Wall clock time since the integration started.
+pub(crate) struct RealTime(Instant);
+
+impl RealTime {
+    pub(crate) fn new() -> Self {
+        Self(Instant::now())
+    }
+}
+
+impl TimeSource for RealTime {
+    fn now(&self) -> f64 {
+        self.0.elapsed().as_secs_f64()
+    }
+}
+
+/// Time that only moves when told to, for stepping animations in tests.
+///
+/// Keep a clone to advance it while the integration reads the original.
+#[derive(Clone, Debug, Default)]
+pub struct ManualTime(Rc<Cell<f64>>);
+
+impl ManualTime {
+    pub fn set(&self, seconds: f64) {
+        self.0.set(seconds);
+    }
+
+    pub fn advance(&self, seconds: f64) {
+        self.0.set(self.0.get() + seconds);
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now(&self) -> f64 {
+        self.0.get()
+    }
+}
@@ -0,0 +1,283 @@
+use crate::wgpu;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use winit::dpi::Position;
+use winit::window::{Theme, UserAttentionType, Window};
+
+#[derive(Debug)]
+enum WindowCommand {
+    SetTitle(String),
+    RequestUserAttention(Option<UserAttentionType>),
+    SetMinimized(bool),
+    SetMaximized(bool),
+    SetAlwaysOnTop(bool),
+    SetVisible(bool),
+    SetCursorGrab(bool),
+    SetCursorVisible(bool),
+    SetCursorPosition(Position),
+    SetImePosition(Position),
+}
+
+/// Lets the app change its window while running.
+///
+/// Clone it into the app and hand the original to the integration through
+/// [`crate::RunOptions::window_handle`]. Requests are applied after `App::update`
+/// returns.
+#[derive(Clone, Debug, Default)]
+pub struct WindowHandle(Rc<Requests>);
+
+#[derive(Debug, Default)]
+struct Requests {
+    commands: RefCell<Vec<WindowCommand>>,
+    screenshot: Cell<bool>,
+    prevent_close: Cell<bool>,
+    close_requested: Cell<bool>,
+    adapter_info: RefCell<Option<wgpu::AdapterInfo>>,
+    output_format: Cell<Option<wgpu::TextureFormat>>,
+    present_mode: Cell<Option<wgpu::PresentMode>>,
+    refresh_rate: Cell<Option<u32>>,
+    theme: Cell<Option<Theme>>,
+    cursor_grabbed: Cell<bool>,
+    cursor_hidden: Cell<bool>,
+    mouse_delta: Cell<(f64, f64)>,
+}
+
+impl WindowHandle {
+    fn push(&self, command: WindowCommand) {
+        self.0.commands.borrow_mut().push(command);
+    }
+
+    /// E.g. to mark unsaved changes with an asterisk.
+    pub fn set_title(&self, title: impl Into<String>) {
+        self.push(WindowCommand::SetTitle(title.into()));
+    }
+
+    /// Flash the taskbar entry or bounce the dock icon. `None` cancels a request.
+    pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+        self.push(WindowCommand::RequestUserAttention(request_type));
+    }
+
+    pub fn set_minimized(&self, minimized: bool) {
+        self.push(WindowCommand::SetMinimized(minimized));
+    }
+
+    pub fn set_maximized(&self, maximized: bool) {
+        self.push(WindowCommand::SetMaximized(maximized));
+    }
+
+    /// Show the window started with [`crate::RunOptions::start_hidden`], or hide
+    /// it again.
+    pub fn set_visible(&self, visible: bool) {
+        self.push(WindowCommand::SetVisible(visible));
+    }
+
+    /// Keep the window above other windows. Not supported on Wayland.
+    pub fn set_always_on_top(&self, always_on_top: bool) {
+        self.push(WindowCommand::SetAlwaysOnTop(always_on_top));
+    }
+
+    /// Confine the cursor to the window, e.g. while drag-rotating a 3D viewport.
+    ///
+    /// egui keeps receiving the cursor position as usual, so it still sees the
+    /// drag start inside the viewport and keeps reporting it as hovered and
+    /// dragged. Read the camera movement from [`Self::mouse_delta`] instead of
+    /// egui's pointer delta, which stops at the window border and jumps on
+    /// every [`Self::set_cursor_position`].
+    pub fn set_cursor_grab(&self, grab: bool) {
+        self.push(WindowCommand::SetCursorGrab(grab));
+    }
+
+    /// Usually hidden while grabbed. The cursor stays hidden when egui changes
+    /// its icon, until the app shows it again.
+    pub fn set_cursor_visible(&self, visible: bool) {
+        self.push(WindowCommand::SetCursorVisible(visible));
+    }
+
+    /// Move the cursor, e.g. back to the center of the viewport each frame of a
+    /// drag. Use `LogicalPosition` for egui points at a zoom factor of 1.
+    pub fn set_cursor_position(&self, position: impl Into<Position>) {
+        self.push(WindowCommand::SetCursorPosition(position.into()));
+    }
+
+    /// Place the IME candidate window, e.g. below the focused text field with
+    /// `PhysicalPosition` of `response.rect.left_bottom() * ctx.pixels_per_point()`.
+    ///
+    /// egui 0.10 doesn't report where its text cursor is, so the integration
+    /// can't do this on its own and the popup otherwise stays where the OS
+    /// puts it.
+    pub fn set_ime_position(&self, position: impl Into<Position>) {
+        self.push(WindowCommand::SetImePosition(position.into()));
+    }
+
+    /// Raw mouse motion since the previous `App::update`, from
+    /// `DeviceEvent::MouseMotion`. Unlike egui's pointer, this isn't affected by
+    /// the window border, cursor warps or pointer acceleration on most
+    /// platforms. While the cursor is grabbed, motion also triggers a redraw.
+    pub fn mouse_delta(&self) -> (f64, f64) {
+        self.0.mouse_delta.get()
+    }
+
+    pub(crate) fn add_mouse_delta(&self, (x, y): (f64, f64)) {
+        let (dx, dy) = self.0.mouse_delta.get();
+        self.0.mouse_delta.set((dx + x, dy + y));
+    }
+
+    pub(crate) fn cursor_grabbed(&self) -> bool {
+        self.0.cursor_grabbed.get()
+    }
+
+    pub(crate) fn cursor_hidden(&self) -> bool {
+        self.0.cursor_hidden.get()
+    }
+
+    /// Capture the frame drawn after the current `App::update` and hand it to
+    /// [`crate::RunOptions::on_screenshot`].
+    pub fn request_screenshot(&self) {
+        self.0.screenshot.set(true);
+    }
+
+    /// With `false`, closing the window only sets [`Self::close_requested`], so the
+    /// app can ask to save changes first. Setting it back to `true` while a close
+    /// is pending closes the window after the current frame.
+    pub fn set_allow_close(&self, allow_close: bool) {
+        self.0.prevent_close.set(!allow_close);
+    }
+
+    /// Whether the user tried to close the window while that wasn't allowed.
+    pub fn close_requested(&self) -> bool {
+        self.0.close_requested.get()
+    }
+
+    /// Keep the window open after a refused close, e.g. if the dialog was cancelled.
+    pub fn cancel_close(&self) {
+        self.0.close_requested.set(false);
+    }
+
+    /// Called on a close request; returns whether the window may close.
+    pub(crate) fn try_close(&self) -> bool {
+        if self.0.prevent_close.get() {
+            self.0.close_requested.set(true);
+            false
+        } else {
+            true
+        }
+    }
+
+    /// A close was refused earlier and the app allows it now.
+    pub(crate) fn should_close(&self) -> bool {
+        self.0.close_requested.get() && !self.0.prevent_close.get()
+    }
+
+    /// The GPU the integration renders with, e.g. for a "Graphics: ..." line in
+    /// bug reports. `None` before the GPU is set up.
+    pub fn adapter_info(&self) -> Option<wgpu::AdapterInfo> {
+        self.0.adapter_info.borrow().clone()
+    }
+
+    pub(crate) fn set_adapter_info(&self, adapter_info: wgpu::AdapterInfo) {
+        *self.0.adapter_info.borrow_mut() = Some(adapter_info);
+    }
+
+    pub(crate) fn output_format(&self) -> Option<wgpu::TextureFormat> {
+        self.0.output_format.get()
+    }
+
+    pub(crate) fn set_output_format(&self, output_format: wgpu::TextureFormat) {
+        self.0.output_format.set(Some(output_format));
+    }
+
+    /// Switch the present mode starting with the next frame, e.g. for a vsync
+    /// checkbox. wgpu can't tell which modes a surface supports; unsupported
+    /// ones fall back to `PresentMode::Fifo` with a logged warning.
+    pub fn set_present_mode(&self, present_mode: wgpu::PresentMode) {
+        self.0.present_mode.set(Some(present_mode));
+    }
+
+    /// Refresh rate in Hz of the monitor the window is on, `None` if unknown.
+    /// Taken as the highest rate the monitor supports at its resolution, since
+    /// winit can't tell the active video mode.
+    pub fn refresh_rate(&self) -> Option<u32> {
+        self.0.refresh_rate.get()
+    }
+
+    pub(crate) fn set_refresh_rate(&self, refresh_rate: Option<u32>) {
+        self.0.refresh_rate.set(refresh_rate);
+    }
+
+    /// The OS theme, `None` where winit can't tell (everywhere but Windows).
+    /// Kept up to date while running.
+    pub fn theme(&self) -> Option<Theme> {
+        self.0.theme.get()
+    }
+
+    pub(crate) fn set_theme(&self, theme: Option<Theme>) {
+        self.0.theme.set(theme);
+    }
+
+    pub(crate) fn take_present_mode(&self) -> Option<wgpu::PresentMode> {
+        self.0.present_mode.take()
+    }
+
+    pub(crate) fn take_screenshot_request(&self) -> bool {
+        self.0.screenshot.replace(false)
+    }
+
+    pub(crate) fn apply(&self, window: &Window) {
+        for command in self.0.commands.borrow_mut().drain(..) {
+            match command {
+                WindowCommand::SetTitle(title) => window.set_title(&title),
+                WindowCommand::RequestUserAttention(request_type) => {
+                    window.request_user_attention(request_type)
+                }
+                WindowCommand::SetMinimized(minimized) => window.set_minimized(minimized),
+                WindowCommand::SetMaximized(maximized) => window.set_maximized(maximized),
+                WindowCommand::SetVisible(visible) => window.set_visible(visible),
+                WindowCommand::SetAlwaysOnTop(always_on_top) => {
+                    warn_if_always_on_top_unsupported(window);
+                    window.set_always_on_top(always_on_top);
+                }
+                WindowCommand::SetCursorGrab(grab) => match window.set_cursor_grab(grab) {
+                    Ok(()) => self.0.cursor_grabbed.set(grab),
+                    Err(e) => log::warn!("Failed to grab the cursor: {}", e),
+                },
+                WindowCommand::SetCursorVisible(visible) => {
+                    self.0.cursor_hidden.set(!visible);
+                    window.set_cursor_visible(visible);
+                }
+                WindowCommand::SetImePosition(position) => window.set_ime_position(position),
+                WindowCommand::SetCursorPosition(position) => {
+                    if let Err(e) = window.set_cursor_position(position) {
+                        log::warn!("Failed to move the cursor: {}", e);
+                    }
+                }
+            }
+        }
+        // The app has seen this frame's motion.
+        self.0.mouse_delta.set((0.0, 0.0));
+    }
+}
+
+/// Wayland has no protocol for it, so winit ignores `always_on_top` there.
+pub(crate) fn warn_if_always_on_top_unsupported(window: &Window) {
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        use winit::platform::unix::WindowExtUnix;
+        if window.wayland_surface().is_some() {
+            log::warn!("Keeping the window on top isn't supported on Wayland");
+        }
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    let _ = window;
+}
@@ -0,0 +1,260 @@
+use crate::{egui, wgpu, GpuOptions, RenderPass, RunError, ScreenDescriptor};
+use egui_winit_platform::{Platform, PlatformDescriptor};
+use std::collections::HashMap;
+use std::time::Instant;
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoopWindowTarget};
+use winit::window::{Window, WindowBuilder, WindowId};
+
+/// Draws the UI of one window managed by a [`WindowManager`].
+pub type WindowUi = Box<dyn FnMut(&egui::CtxRef)>;
+
+/// Everything needed to draw egui into one window.
+///
+/// Fields are dropped in declaration order, so the surface goes before its window.
+struct ManagedWindow {
+    swap_chain: wgpu::SwapChain,
+    surface: wgpu::Surface,
+    sc_desc: wgpu::SwapChainDescriptor,
+    platform: Platform,
+    render_pass: RenderPass,
+    ui: WindowUi,
+    cursor_icon: egui::CursorIcon,
+    window: Window,
+}
+
+impl ManagedWindow {
+    fn new(
+        window: Window,
+        surface: wgpu::Surface,
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        ui: WindowUi,
+    ) -> Self {
+        let format = adapter.get_swap_chain_preferred_format(&surface);
+        let size = window.inner_size();
+        let sc_desc = wgpu::SwapChainDescriptor {
+            usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
+            format,
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu::PresentMode::Mailbox,
+        };
+        let swap_chain = device.create_swap_chain(&surface, &sc_desc);
+        let platform = Platform::new(PlatformDescriptor {
+            physical_width: size.width,
+            physical_height: size.height,
+            scale_factor: window.scale_factor(),
+            font_definitions: Default::default(),
+            style: Default::default(),
+        });
+        Self {
+            swap_chain,
+            surface,
+            sc_desc,
+            platform,
+            render_pass: RenderPass::new(device, format),
+            ui,
+            cursor_icon: egui::CursorIcon::Default,
+            window,
+        }
+    }
+
+    fn resize(&mut self, device: &wgpu::Device, size: winit::dpi::PhysicalSize<u32>) {
+        self.sc_desc.width = size.width;
+        self.sc_desc.height = size.height;
+        if size.width != 0 && size.height != 0 {
+            self.swap_chain = device.create_swap_chain(&self.surface, &self.sc_desc);
+        }
+    }
+
+    fn redraw(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, start_time: Instant) {
+        if self.sc_desc.width == 0 || self.sc_desc.height == 0 {
+            return;
+        }
+        self.platform
+            .update_time(start_time.elapsed().as_secs_f64());
+        let output_frame = match self.swap_chain.get_current_frame() {
+            Ok(frame) => frame,
+            Err(e) => {
+                crate::dropped_frame::log(&e);
+                if crate::dropped_frame::needs_new_swap_chain(&e) {
+                    self.swap_chain = device.create_swap_chain(&self.surface, &self.sc_desc);
+                    self.window.request_redraw();
+                }
+                return;
+            }
+        };
+
+        self.platform.begin_frame();
+        (self.ui)(&self.platform.context());
+        let (egui_output, shapes) = self.platform.end_frame();
+        if egui_output.cursor_icon != self.cursor_icon {
+            self.cursor_icon = egui_output.cursor_icon;
+            crate::cursor::set_cursor_icon(&self.window, self.cursor_icon, false);
+        }
+        let clipped_meshes = self.platform.context().tessellate(shapes);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("egui encoder"),
+        });
+        let screen_descriptor = ScreenDescriptor {
+            physical_width: self.sc_desc.width,
+            physical_height: self.sc_desc.height,
+            scale_factor: self.window.scale_factor() as f32,
+        };
+        self.render_pass
+            .update_texture(device, queue, &self.platform.context().texture());
+        self.render_pass.update_user_textures(device, queue);
+        self.render_pass
+            .update_buffers(device, queue, &clipped_meshes, &screen_descriptor);
+        self.render_pass.execute(
+            &mut encoder,
+            &output_frame.output.view,
+            &clipped_meshes,
+            &screen_descriptor,
+            Some(wgpu::Color::BLACK),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+        if egui_output.needs_repaint {
+            self.window.request_redraw();
+        }
+    }
+}
+
+/// Several windows sharing one device, each with its own surface, swap chain,
+/// [`Platform`] and [`RenderPass`].
+///
+/// Feed every event of the loop to [`Self::handle_event`]; window events are
+/// routed to the window they belong to. Windows can be added from inside the
+/// loop with the `EventLoopWindowTarget` it passes in.
+pub struct WindowManager {
+    windows: HashMap<WindowId, ManagedWindow>,
+    queue: wgpu::Queue,
+    device: wgpu::Device,
+    adapter: wgpu::Adapter,
+    instance: wgpu::Instance,
+    start_time: Instant,
+}
+
+impl WindowManager {
+    /// Open the first window and set up the device all windows share.
+    pub fn new<T>(
+        event_loop: &EventLoopWindowTarget<T>,
+        title: &str,
+        ui: WindowUi,
+        gpu: GpuOptions,
+    ) -> Result<Self, RunError> {
+        let window = WindowBuilder::new()
+            .with_title(title)
+            .build(event_loop)
+            .map_err(RunError::WindowBuild)?;
+        let instance = wgpu::Instance::new(gpu.backends);
+        let surface = unsafe { instance.create_surface(&window) };
+        let adapter = crate::request_adapter(&instance, &gpu, Some(&surface))?;
+        let (device, queue) = crate::request_device(&adapter, gpu.features, gpu.limits)?;
+
+        let id = window.id();
+        let first = ManagedWindow::new(window, surface, &adapter, &device, ui);
+        let mut windows = HashMap::new();
+        windows.insert(id, first);
+        Ok(Self {
+            windows,
+            queue,
+            device,
+            adapter,
+            instance,
+            start_time: Instant::now(),
+        })
+    }
+
+    /// Open another window drawing `ui`.
+    pub fn add_window<T>(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<T>,
+        title: &str,
+        ui: WindowUi,
+    ) -> Result<WindowId, RunError> {
+        let window = WindowBuilder::new()
+            .with_title(title)
+            .build(event_loop)
+            .map_err(RunError::WindowBuild)?;
+        let surface = unsafe { self.instance.create_surface(&window) };
+        let id = window.id();
+        self.windows.insert(
+            id,
+            ManagedWindow::new(window, surface, &self.adapter, &self.device, ui),
+        );
+        Ok(id)
+    }
+
+    /// Close a window. Does nothing if it's already gone.
+    pub fn remove_window(&mut self, id: WindowId) {
+        self.windows.remove(&id);
+    }
+
+    /// `true` once every window was closed.
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    pub fn handle_event<T>(&mut self, event: &Event<T>) {
+        match event {
+            Event::WindowEvent {
+                window_id,
+                event: window_event,
+            } => {
+                let managed = match self.windows.get_mut(window_id) {
+                    Some(managed) => managed,
+                    None => return,
+                };
+                managed.platform.handle_event(event);
+                match window_event {
+                    WindowEvent::Resized(size) => managed.resize(&self.device, *size),
+                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                        managed.resize(&self.device, **new_inner_size)
+                    }
+                    WindowEvent::CloseRequested => {
+                        self.windows.remove(window_id);
+                    }
+                    _ => (),
+                }
+            }
+            Event::RedrawRequested(window_id) => {
+                if let Some(managed) = self.windows.get_mut(window_id) {
+                    managed.redraw(&self.device, &self.queue, self.start_time);
+                }
+            }
+            Event::MainEventsCleared => {
+                for managed in self.windows.values() {
+                    managed.window.request_redraw();
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Run several windows, each drawing its own UI, until all of them are closed.
+/// Only returns if setting up a window or the GPU fails.
+pub fn run_windows(windows: Vec<(String, WindowUi)>, gpu: GpuOptions) -> Result<(), RunError> {
+    let event_loop = winit::event_loop::EventLoop::new();
+    let mut windows = windows.into_iter();
+    let (title, ui) = match windows.next() {
+        Some(first) => first,
+        None => return Ok(()),
+    };
+    let mut manager = WindowManager::new(&event_loop, &title, ui, gpu)?;
+    for (title, ui) in windows {
+        manager.add_window(&event_loop, &title, ui)?;
+    }
+
+    event_loop.run(move |event, _, control_flow| {
+        manager.handle_event(&event);
+        *control_flow = if manager.is_empty() {
+            ControlFlow::Exit
+        } else {
+            ControlFlow::Wait
+        };
+    })
+}
@@ -0,0 +1,47 @@
+use winit::dpi::{LogicalSize, PhysicalPosition};
+use winit::window::{Window, WindowBuilder};
+
+/// Window geometry that can be saved on exit and restored on the next launch.
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct WindowSettings {
+    /// Outer position of the window in physical pixels.
+    pos: Option<[i32; 2]>,
+    /// Inner size of the window in logical pixels.
+    inner_size_points: Option<[f64; 2]>,
+}
+
+impl WindowSettings {
+    pub fn from_json_file(settings_json_path: impl AsRef<std::path::Path>) -> Option<Self> {
+        crate::persistence::read_json(settings_json_path)
+    }
+
+    /// Capture the current geometry of a displayed window.
+    ///
+    /// `None` while the window is minimized: it then has a 0x0 size and, on
+    /// Windows, sits at (-32000, -32000), where restoring it would hide it.
+    pub fn from_display(window: &Window) -> Option<Self> {
+        let inner_size = window.inner_size();
+        if inner_size.width == 0 || inner_size.height == 0 {
+            return None;
+        }
+        let inner_size = inner_size.to_logical::<f64>(window.scale_factor());
+        Some(Self {
+            pos: window.outer_position().ok().map(|pos| [pos.x, pos.y]),
+            inner_size_points: Some([inner_size.width, inner_size.height]),
+        })
+    }
+
+    pub fn initialize_size(&self, window: WindowBuilder) -> WindowBuilder {
+        if let Some([width, height]) = self.inner_size_points {
+            window.with_inner_size(LogicalSize { width, height })
+        } else {
+            window
+        }
+    }
+
+    pub fn restore_positions(&self, window: &Window) {
+        if let Some([x, y]) = self.pos {
+            window.set_outer_position(PhysicalPosition { x, y });
+        }
+    }
+}